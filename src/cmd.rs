@@ -2,9 +2,12 @@ use std::error::Error;
 
 use clap::{Parser, Subcommand};
 
+pub mod check;
+pub mod export;
 pub mod function;
 pub mod generate;
 pub mod run;
+pub mod schema;
 
 #[derive(Debug, Parser)]
 #[command(name = "yurl", subcommand_required = true)]
@@ -37,6 +40,15 @@ pub enum Commands {
     Function(function::FunctionArg),
     #[command(name = "generate", long_flag = "gen", short_flag = 'g')]
     Generate(generate::GenerateArg),
+    /// validate a template without sending requests
+    #[command(name = "check")]
+    Check(check::CheckArg),
+    /// render the requests of a template in another format
+    #[command(name = "export", subcommand_required = true)]
+    Export(export::ExportArg),
+    /// print the JSON Schema of templates
+    #[command(name = "schema")]
+    Schema(schema::SchemaArg),
 }
 
 pub trait Execute {
@@ -0,0 +1,37 @@
+use std::error::Error;
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::core::error::YurlError;
+use crate::core::lint;
+use crate::{error, success, yurl_error};
+
+use super::Execute;
+
+/// validate a template and its imports without sending any request.
+#[derive(Args, Debug)]
+#[command(version, about, long_about = None)]
+pub struct CheckArg {
+    #[arg(short, long, default_value = "template.yaml")]
+    pub file: String,
+}
+
+impl Execute for CheckArg {
+    fn run(self) -> Result<(), Box<dyn Error>> {
+        let (problems, requests) = lint::check(&self.file);
+        if problems.is_empty() {
+            success!(format!("{}: {} requests, no problems", self.file, requests));
+            return Ok(());
+        }
+        for problem in &problems {
+            error!(problem.to_string());
+        }
+        let plural = if problems.len() == 1 { "" } else { "s" };
+        Err(yurl_error!(&format!(
+            "{} problem{} found",
+            problems.len(),
+            plural
+        )))
+    }
+}
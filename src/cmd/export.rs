@@ -0,0 +1,55 @@
+use std::error::Error;
+
+use clap::{Args, Subcommand};
+
+use super::run::{RenderFormat, TemplateArg};
+use super::Execute;
+
+#[derive(Debug, Args)]
+#[command(flatten_help = true)]
+pub struct ExportArg {
+    #[command(subcommand)]
+    pub command: ExportCommands,
+}
+
+impl Execute for ExportArg {
+    fn run(self) -> Result<(), Box<dyn Error>> {
+        match self.command {
+            ExportCommands::Curl(arg) => arg.render(&RenderFormat::CURL),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCommands {
+    /// print the requests as curl commands without sending them,
+    /// `${res...}` of requests not executed are left as <res.name.path>
+    Curl(TemplateArg),
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use crate::cmd::{Cli, Commands};
+
+    #[test]
+    fn test_curl_args() {
+        let cli =
+            Cli::try_parse_from(["yurl", "export", "curl", "-f", "a.yaml", "--only", "login"])
+                .unwrap();
+        assert!(matches!(cli.command, Commands::Export(_)));
+        for flag in [
+            "--output",
+            "--report",
+            "--session",
+            "--dry-run",
+            "--as-curl",
+        ] {
+            assert!(
+                Cli::try_parse_from(["yurl", "export", "curl", flag, "json"]).is_err(),
+                "{flag} accepted"
+            );
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use std::error::Error;
+use std::fs;
+
+use clap::Args;
+use colored::Colorize;
+
+use crate::core::schema;
+use crate::success;
+
+use super::Execute;
+
+/// print the JSON Schema of the template yaml for editor completion.
+#[derive(Args, Debug)]
+#[command(version, about, long_about = None)]
+pub struct SchemaArg {
+    /// write the schema to this file instead of stdout
+    #[arg(long, short)]
+    pub out: Option<String>,
+}
+
+impl Execute for SchemaArg {
+    fn run(self) -> Result<(), Box<dyn Error>> {
+        let schema = schema::schema();
+        match &self.out {
+            Some(out) => {
+                fs::write(out, format!("{}\n", schema))?;
+                success!(format!("please view {}", out));
+            }
+            None => println!("{}", schema),
+        }
+        Ok(())
+    }
+}
@@ -7,28 +7,51 @@ use std::{env, fs};
 use clap::error::Result;
 use colored::Colorize;
 use md5::Digest;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::core::request::Request;
-use crate::{success, yurl_error};
+use crate::{info, yurl_error};
 
+use self::client::Defaults;
 use self::error::YurlError;
 
+pub mod auth;
+pub mod client;
+pub mod compress;
+pub mod curl;
 pub mod error;
 pub mod expression;
 pub mod function;
 pub mod json;
+pub mod lint;
 pub mod log;
 pub mod multipart;
+pub mod render;
+pub mod report;
 pub mod request;
+pub mod schema;
+pub mod secret;
+pub mod select;
+pub mod session;
+pub mod sign;
+pub mod tls;
+pub mod unix;
 pub mod yaml;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// a yurl yaml file, imports are merged into it.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Template {
     #[serde(default)]
     pub imports: Vec<String>,
     #[serde(default)]
+    #[schemars(with = "serde_json::Value")]
     pub vars: serde_yaml::Value,
+    /// variable names masked as `***` in every output.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Defaults::is_empty")]
+    pub defaults: Defaults,
     #[serde(default)]
     pub requests: Vec<Request>,
     #[serde(skip)]
@@ -45,6 +68,12 @@ impl Template {
             if !t.vars.is_null() {
                 template.variables.push(t.vars);
             }
+            for secret in t.secrets {
+                if !template.secrets.contains(&secret) {
+                    template.secrets.push(secret);
+                }
+            }
+            template.defaults = template.defaults.merge(&t.defaults);
             for r in t.requests {
                 if template.requests.contains(&r) {
                     return Err(yurl_error!(&format!("duplicated request: {}", &r.name)));
@@ -70,9 +99,12 @@ impl Template {
             env::set_current_dir(current_dir)?;
             return Ok(templates);
         }
-        success!(format!("parse file: {}", file));
+        info!(format!("parse file: {}", file));
         parsed_file.borrow_mut().push(digest);
-        let template: Template = serde_yaml::from_str(&yaml)?;
+        let mut template: Template = serde_yaml::from_str(&yaml)?;
+        for request in template.requests.iter_mut() {
+            request.file = file.to_string();
+        }
         if template.imports.is_empty() {
             env::set_current_dir(current_dir)?;
             templates.push(template);
@@ -97,6 +129,8 @@ impl Default for Template {
         Self {
             imports: Vec::default(),
             vars: serde_yaml::Value::default(),
+            secrets: Vec::default(),
+            defaults: Defaults::default(),
             requests: Vec::default(),
             variables: Vec::default(),
         }
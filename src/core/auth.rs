@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use rand::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::error::YurlError;
+use crate::yurl_error;
+
+const AUTHORIZATION_KEY: &str = "Authorization";
+/// refresh oauth2 tokens a little before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(10);
+
+/// authentication of a request, set on a request or in template `defaults`.
+/// every value can use expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum Auth {
+    BASIC {
+        username: String,
+        password: String,
+    },
+    BEARER {
+        token: String,
+    },
+    APIKEY {
+        name: String,
+        value: String,
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+    /// answers the `WWW-Authenticate: Digest` challenge of a 401 response.
+    DIGEST {
+        username: String,
+        password: String,
+    },
+    /// fetches a token from token_url, cached and refreshed for the whole run.
+    OAUTH2 {
+        grant: Grant,
+        token_url: String,
+        client_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_secret: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        username: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scope: Option<String>,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum ApiKeyLocation {
+    #[default]
+    HEADER,
+    QUERY,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum Grant {
+    CLIENT_CREDENTIALS,
+    PASSWORD,
+}
+
+impl Auth {
+    /// whether a 401 response should be answered by sending the request again.
+    pub fn retry_unauthorized(&self) -> bool {
+        matches!(self, Auth::DIGEST { .. } | Auth::OAUTH2 { .. })
+    }
+
+    /// add the credentials to the request
+    ///
+    /// * query whether query credentials are added, redirected urls already carry them
+    /// * challenge WWW-Authenticate header of a previous 401 response
+    pub fn apply(
+        &self,
+        mut request: ureq::Request,
+        tokens: &mut Tokens,
+        agent: &ureq::Agent,
+        query: bool,
+        challenge: Option<&str>,
+    ) -> Result<ureq::Request, Box<dyn Error>> {
+        match self {
+            Auth::BASIC { username, password } => {
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{username}:{password}"));
+                request = request.set(AUTHORIZATION_KEY, &format!("Basic {credentials}"));
+            }
+            Auth::BEARER { token } => {
+                request = request.set(AUTHORIZATION_KEY, &format!("Bearer {token}"));
+            }
+            Auth::APIKEY {
+                name,
+                value,
+                location,
+            } => match location {
+                ApiKeyLocation::HEADER => request = request.set(name, value),
+                ApiKeyLocation::QUERY if query => request = request.query(name, value),
+                ApiKeyLocation::QUERY => {}
+            },
+            Auth::DIGEST { username, password } => {
+                if let Some(challenge) = challenge {
+                    let url = url::Url::parse(request.url())?;
+                    let uri = match url.query() {
+                        Some(query) => format!("{}?{}", url.path(), query),
+                        None => url.path().to_string(),
+                    };
+                    let header =
+                        digest_header(challenge, username, password, request.method(), &uri)?;
+                    request = request.set(AUTHORIZATION_KEY, &header);
+                }
+            }
+            Auth::OAUTH2 { .. } => {
+                let token = tokens.token(self, agent)?;
+                request = request.set(AUTHORIZATION_KEY, &format!("Bearer {token}"));
+            }
+        }
+        Ok(request)
+    }
+}
+
+/// oauth2 access token cache of a run.
+#[derive(Default)]
+pub struct Tokens {
+    tokens: HashMap<String, Token>,
+}
+
+struct Token {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<Instant>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<u64>,
+}
+
+impl Tokens {
+    /// access token of the auth, fetched or refreshed when missing or expired.
+    fn token(&mut self, auth: &Auth, agent: &ureq::Agent) -> Result<String, Box<dyn Error>> {
+        let key = serde_json::to_string(auth)?;
+        let refresh_token = match self.tokens.get(&key) {
+            Some(token)
+                if token
+                    .expires_at
+                    .is_none_or(|at| Instant::now() + TOKEN_EXPIRY_MARGIN < at) =>
+            {
+                return Ok(token.access_token.clone());
+            }
+            Some(token) => token.refresh_token.clone(),
+            None => None,
+        };
+        let token = match refresh_token {
+            // a rejected refresh token falls back to a new grant
+            Some(refresh_token) => fetch_token(auth, agent, Some(&refresh_token))
+                .or_else(|_| fetch_token(auth, agent, None))?,
+            None => fetch_token(auth, agent, None)?,
+        };
+        let access_token = token.access_token.clone();
+        self.tokens.insert(key, token);
+        Ok(access_token)
+    }
+
+    /// forget the token of the auth, so the next request fetches a new one.
+    pub fn invalidate(&mut self, auth: &Auth) {
+        if let Ok(key) = serde_json::to_string(auth) {
+            self.tokens.remove(&key);
+        }
+    }
+}
+
+fn fetch_token(
+    auth: &Auth,
+    agent: &ureq::Agent,
+    refresh_token: Option<&str>,
+) -> Result<Token, Box<dyn Error>> {
+    let Auth::OAUTH2 {
+        grant,
+        token_url,
+        client_id,
+        client_secret,
+        username,
+        password,
+        scope,
+    } = auth
+    else {
+        return Err(yurl_error!("not an oauth2 auth"));
+    };
+    let mut form: Vec<(&str, &str)> = vec![("client_id", client_id)];
+    if let Some(client_secret) = client_secret {
+        form.push(("client_secret", client_secret));
+    }
+    match (refresh_token, grant) {
+        (Some(refresh_token), _) => {
+            form.push(("grant_type", "refresh_token"));
+            form.push(("refresh_token", refresh_token));
+        }
+        (None, Grant::CLIENT_CREDENTIALS) => form.push(("grant_type", "client_credentials")),
+        (None, Grant::PASSWORD) => {
+            let (Some(username), Some(password)) = (username, password) else {
+                return Err(yurl_error!(
+                    "oauth2 password grant requires username and password"
+                ));
+            };
+            form.push(("grant_type", "password"));
+            form.push(("username", username));
+            form.push(("password", password));
+        }
+    }
+    if let Some(scope) = scope {
+        form.push(("scope", scope));
+    }
+    let response: TokenResponse = match agent.post(token_url).send_form(&form) {
+        Ok(response) => response.into_json()?,
+        Err(ureq::Error::Status(status, response)) => {
+            return Err(yurl_error!(&format!(
+                "oauth2 token request [{}] fail, status code: {}, message: {}",
+                token_url,
+                status,
+                response.into_string().unwrap_or_default()
+            )))
+        }
+        Err(e) => return Err(Box::new(e)),
+    };
+    Ok(Token {
+        access_token: response.access_token,
+        refresh_token: response
+            .refresh_token
+            .or_else(|| refresh_token.map(|t| t.to_string())),
+        expires_at: response
+            .expires_in
+            .map(|secs| Instant::now() + Duration::from_secs(secs)),
+    })
+}
+
+/// Authorization header answering a digest challenge, RFC 7616.
+fn digest_header(
+    challenge: &str,
+    username: &str,
+    password: &str,
+    method: &str,
+    uri: &str,
+) -> Result<String, Box<dyn Error>> {
+    let params = challenge_params(challenge)
+        .ok_or_else(|| -> Box<dyn Error> { yurl_error!("not a digest challenge") })?;
+    let realm = params.get("realm").map(|r| r.as_str()).unwrap_or_default();
+    let nonce = params
+        .get("nonce")
+        .ok_or_else(|| -> Box<dyn Error> { yurl_error!("digest challenge without nonce") })?;
+    let algorithm = params.get("algorithm").map(|a| a.as_str()).unwrap_or("MD5");
+    let hash: fn(&str) -> String = match algorithm.trim_end_matches("-sess") {
+        a if a.eq_ignore_ascii_case("MD5") => |s| format!("{:x}", md5::compute(s)),
+        a if a.eq_ignore_ascii_case("SHA-256") => |s| format!("{:x}", Sha256::digest(s)),
+        _ => {
+            return Err(yurl_error!(&format!(
+                "digest algorithm not supported: {}",
+                algorithm
+            )))
+        }
+    };
+    let cnonce: String = rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+    let nc = "00000001";
+    let mut ha1 = hash(&format!("{username}:{realm}:{password}"));
+    if algorithm.ends_with("-sess") {
+        ha1 = hash(&format!("{ha1}:{nonce}:{cnonce}"));
+    }
+    let ha2 = hash(&format!("{method}:{uri}"));
+    let qop = params
+        .get("qop")
+        .filter(|qop| qop.split(',').any(|q| q.trim() == "auth"));
+    let response = match qop {
+        Some(_) => hash(&format!("{ha1}:{nonce}:{nc}:{cnonce}:auth:{ha2}")),
+        None => hash(&format!("{ha1}:{nonce}:{ha2}")),
+    };
+    let mut header = format!(
+        "Digest username=\"{username}\", realm=\"{realm}\", nonce=\"{nonce}\", uri=\"{uri}\", algorithm={algorithm}, response=\"{response}\""
+    );
+    if qop.is_some() {
+        header.push_str(&format!(", qop=auth, nc={nc}, cnonce=\"{cnonce}\""));
+    }
+    if let Some(opaque) = params.get("opaque") {
+        header.push_str(&format!(", opaque=\"{opaque}\""));
+    }
+    Ok(header)
+}
+
+/// parameters of a `Digest k=v, k="v"` challenge.
+fn challenge_params(challenge: &str) -> Option<HashMap<String, String>> {
+    let (scheme, rest) = challenge.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("Digest") {
+        return None;
+    }
+    let mut params = HashMap::new();
+    let mut rest = rest.trim();
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let value = value.trim_start();
+        let (value, remain) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                (&quoted[..end], quoted.get(end + 1..).unwrap_or_default())
+            }
+            None => {
+                let end = value.find(',').unwrap_or(value.len());
+                (value[..end].trim(), &value[end..])
+            }
+        };
+        params.insert(key, value.to_string());
+        rest = remain.trim_start_matches([',', ' ']);
+    }
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{challenge_params, digest_header};
+
+    #[test]
+    fn test_challenge_params() {
+        let params = challenge_params(
+            r#"Digest realm="test@example.com", qop="auth,auth-int", nonce="abc", algorithm=MD5"#,
+        )
+        .unwrap();
+        assert_eq!(params["realm"], "test@example.com");
+        assert_eq!(params["qop"], "auth,auth-int");
+        assert_eq!(params["nonce"], "abc");
+        assert_eq!(params["algorithm"], "MD5");
+        assert!(challenge_params("Basic realm=\"x\"").is_none());
+    }
+
+    #[test]
+    fn test_digest_header() {
+        // RFC 2617 example without qop
+        let header = digest_header(
+            r#"Digest realm="testrealm@host.com", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093""#,
+            "Mufasa",
+            "Circle Of Life",
+            "GET",
+            "/dir/index.html",
+        )
+        .unwrap();
+        assert!(header.contains("response=\"670fd8c2df070c60b045671b8b24ff02\""));
+    }
+}
@@ -0,0 +1,113 @@
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+
+use flate2::bufread::{DeflateDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Accept-Encoding sent unless the request sets one, gzip and br are decoded by ureq.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+pub const CONTENT_ENCODING_KEY: &str = "Content-Encoding";
+
+/// compression of the request body, sent as Content-Encoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum Compress {
+    GZIP,
+    DEFLATE,
+}
+
+impl Compress {
+    pub fn content_encoding(&self) -> &'static str {
+        match self {
+            Compress::GZIP => "gzip",
+            Compress::DEFLATE => "deflate",
+        }
+    }
+
+    pub fn compress(&self, body: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            Compress::GZIP => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+            Compress::DEFLATE => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()?
+            }
+        })
+    }
+}
+
+/// response body reader, deflate bodies are inflated here since ureq leaves them.
+pub fn body_reader(response: ureq::Response) -> Box<dyn Read + Send + Sync> {
+    let deflate = response
+        .header(CONTENT_ENCODING_KEY)
+        .is_some_and(|e| e.trim().eq_ignore_ascii_case("deflate"));
+    let reader = response.into_reader();
+    match deflate {
+        true => inflate(reader),
+        false => reader,
+    }
+}
+
+/// response body decoded with its charset, see body_reader.
+pub fn body_string(response: ureq::Response) -> Result<String, Box<dyn Error>> {
+    Ok(body_bytes(response)?.1)
+}
+
+/// response body bytes and the body decoded with its charset.
+pub fn body_bytes(response: ureq::Response) -> Result<(Vec<u8>, String), Box<dyn Error>> {
+    let encoding = encoding_rs::Encoding::for_label(response.charset().as_bytes())
+        .unwrap_or(encoding_rs::UTF_8);
+    let mut body = Vec::new();
+    body_reader(response).read_to_end(&mut body)?;
+    let decoded = encoding.decode(&body).0.into_owned();
+    Ok((body, decoded))
+}
+
+/// HTTP deflate is zlib wrapped, some servers send raw deflate instead.
+fn inflate<R: Read + Send + Sync + 'static>(reader: R) -> Box<dyn Read + Send + Sync> {
+    let mut reader = BufReader::new(reader);
+    let zlib = match reader.fill_buf() {
+        Ok([cmf, flg, ..]) => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    };
+    match zlib {
+        true => Box::new(ZlibDecoder::new(reader)),
+        false => Box::new(DeflateDecoder::new(reader)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    use super::{inflate, Compress};
+
+    fn deflate_raw(body: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(body).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_inflate() {
+        let body = br#"{"name":"yurl"}"#;
+        for compressed in [Compress::DEFLATE.compress(body).unwrap(), deflate_raw(body)] {
+            let mut inflated = Vec::new();
+            inflate(std::io::Cursor::new(compressed))
+                .read_to_end(&mut inflated)
+                .unwrap();
+            assert_eq!(inflated, body);
+        }
+        let gzip = Compress::GZIP.compress(body).unwrap();
+        assert_eq!(&gzip[..2], &[0x1f, 0x8b]);
+    }
+}
@@ -0,0 +1,949 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use serde_yaml::Mapping;
+
+use crate::core::auth::{ApiKeyLocation, Auth};
+use crate::core::client::{parse_duration, Defaults};
+use crate::core::error::YurlError;
+use crate::core::multipart::Part;
+use crate::core::request::{
+    scalar_to_string, ContentType, Request, ResponseType, DEFAULT_REDIRECTS,
+};
+use crate::core::secret::{Secrets, MASK};
+use crate::core::sign::Sign;
+use crate::core::tls::Tls;
+use crate::core::unix;
+use crate::yurl_error;
+
+const CONTENT_TYPE: &str = "Content-Type";
+
+/// curl command line sending the resolved request, one option per line.
+/// credential headers, cookies and the secrets are masked, the command is meant to be shared.
+pub fn command(
+    request: &Request,
+    defaults: &Defaults,
+    secrets: &Secrets,
+) -> Result<String, Box<dyn Error>> {
+    let config = defaults.config.merge(&request.config);
+    let auth = request.auth.as_ref().or(defaults.auth.as_ref());
+    let sign = request.sign.as_ref().or(defaults.sign.as_ref());
+    let mut comments = Vec::new();
+    let mut args: Vec<String> = vec!["curl".to_string()];
+    let mut push = |option: &str, value: Option<&str>| {
+        match value {
+            Some(value) => args.push(format!("{} {}", option, quote(value))),
+            None => args.push(option.to_string()),
+        };
+    };
+
+    let (socket, url) = match unix::split_url(&request.url)? {
+        (Some(socket), url) => (Some(socket), url),
+        (None, url) if config.unix_socket.is_some() && url.starts_with('/') => {
+            (config.unix_socket.clone(), unix::local_url(&url))
+        }
+        (None, url) => (config.unix_socket.clone(), url),
+    };
+    // the url is kept as written, <res.name.path> placeholders included
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if request.content_type == ContentType::URLENCODED {
+        query.extend_pairs(
+            request
+                .array_format
+                .flatten(&request.params, ContentType::URLENCODED.to_kv().1)?,
+        );
+    }
+    if let Some(Auth::APIKEY {
+        name,
+        value,
+        location: ApiKeyLocation::QUERY,
+    }) = auth
+    {
+        query.append_pair(name, value);
+    }
+    let query = query.finish();
+    let url = match (query.is_empty(), url.contains('?')) {
+        (true, _) => url,
+        (false, true) => format!("{}&{}", url, query),
+        (false, false) => format!("{}?{}", url, query),
+    };
+
+    let method = request.method.as_str();
+    if method != "GET" {
+        push("-X", Some(method));
+    }
+    push(&quote(&url), None);
+    if let Some(socket) = &socket {
+        push("--unix-socket", Some(socket));
+    }
+
+    // Content-Type of FILE carries the boundary chosen by curl
+    if matches!(request.content_type, ContentType::JSON | ContentType::FORM) {
+        let (key, value) = request.content_type.to_kv();
+        push("-H", Some(&format!("{}: {}", key, value)));
+    }
+    let mut headers: Vec<(&String, &String)> = request.headers.iter().collect();
+    headers.sort();
+    for (key, value) in headers {
+        push(
+            "-H",
+            Some(&format!("{}: {}", key, secrets.mask_header(key, value))),
+        );
+    }
+    if !request.cookies.is_empty() {
+        let mut cookies: Vec<String> = request
+            .cookies
+            .keys()
+            .map(|k| format!("{}={}", k, MASK))
+            .collect();
+        cookies.sort();
+        push("-b", Some(&cookies.join("; ")));
+    }
+
+    match auth {
+        Some(Auth::BASIC { username, password }) => {
+            push("-u", Some(&format!("{}:{}", username, password)))
+        }
+        Some(Auth::DIGEST { username, password }) => {
+            push("--digest", None);
+            push("-u", Some(&format!("{}:{}", username, password)));
+        }
+        Some(Auth::BEARER { token }) => {
+            push("-H", Some(&format!("Authorization: Bearer {}", token)))
+        }
+        Some(Auth::APIKEY {
+            name,
+            value,
+            location: ApiKeyLocation::HEADER,
+        }) => push("-H", Some(&format!("{}: {}", name, value))),
+        Some(Auth::OAUTH2 { token_url, .. }) => {
+            comments.push(format!("# the oauth2 token is fetched from {}", token_url));
+            push("-H", Some("Authorization: Bearer <oauth2 token>"));
+        }
+        _ => {}
+    }
+    match sign {
+        Some(Sign::AWS4 {
+            access_key,
+            session_token,
+            region,
+            service,
+            ..
+        }) => {
+            push(
+                "--aws-sigv4",
+                Some(&format!("aws:amz:{}:{}", region, service)),
+            );
+            // the keys are read from the environment, never written in the command
+            let mut variables = vec!["AWS_SECRET_ACCESS_KEY"];
+            let user = quote(&format!("{}:", access_key));
+            push(&format!("--user {}\"$AWS_SECRET_ACCESS_KEY\"", user), None);
+            if session_token.is_some() {
+                variables.push("AWS_SESSION_TOKEN");
+                push("-H \"x-amz-security-token: $AWS_SESSION_TOKEN\"", None);
+            }
+            comments.push(format!(
+                "# export {} before running",
+                variables.join(" and ")
+            ));
+        }
+        Some(Sign::HMAC { header, .. }) => {
+            comments.push(format!(
+                "# the {} hmac signature is computed when the request is sent",
+                header
+            ));
+        }
+        None => {}
+    }
+
+    if let Some(compress) = &request.compress {
+        comments.push(format!(
+            "# yurl sends the body with Content-Encoding: {}, curl sends it as is",
+            compress.content_encoding()
+        ));
+    }
+    match request.content_type {
+        ContentType::URLENCODED => {}
+        ContentType::JSON => push("--data-raw", Some(&serde_json::to_string(&request.params)?)),
+        ContentType::FORM => {
+            let body = request
+                .array_format
+                .flatten(&request.params, ContentType::FORM.to_kv().1)?;
+            let body = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(body)
+                .finish();
+            push("--data-raw", Some(&body));
+        }
+        ContentType::FILE => {
+            let parts = request.array_format.flatten_values(
+                &request.params,
+                ContentType::FILE.to_kv().1,
+                Part::is_part,
+            )?;
+            for (key, value) in parts {
+                if Part::is_part(&value) {
+                    let part: Part = serde_yaml::from_value(value.into_owned())?;
+                    let mut form = match (&part.file, &part.text, &part.json) {
+                        (Some(file), _, _) => format!("{}=@{}", key, file),
+                        // sent as is, without the @file, <file and ; handling of -F
+                        (_, Some(text), _)
+                            if part.filename.is_none() && part.content_type.is_none() =>
+                        {
+                            push("--form-string", Some(&format!("{}={}", key, text)));
+                            continue;
+                        }
+                        (_, Some(text), _) => format!("{}={}", key, form_quote(text)),
+                        (_, _, Some(json)) => format!("{}={}", key, form_quote(&json.to_string())),
+                        _ => {
+                            comments.push(format!("# base64 part {} is not included", key));
+                            continue;
+                        }
+                    };
+                    if let Some(filename) = &part.filename {
+                        form.push_str(&format!(";filename={}", form_quote(filename)));
+                    }
+                    match &part.content_type {
+                        Some(content_type) => form.push_str(&format!(";type={}", content_type)),
+                        None if part.json.is_some() => form.push_str(";type=application/json"),
+                        None => {}
+                    }
+                    push("-F", Some(&form));
+                    continue;
+                }
+                let value = scalar_to_string(&value).unwrap_or_default();
+                match value
+                    .strip_prefix("FILE(")
+                    .and_then(|v| v.strip_suffix(')'))
+                {
+                    Some(path) => push("-F", Some(&format!("{}=@{}", key, path))),
+                    // not read as @file or <file by curl
+                    None => push("--form-string", Some(&format!("{}={}", key, value))),
+                }
+            }
+        }
+    }
+
+    if let Some(timeout) = &config.timeout {
+        push("--max-time", Some(&seconds(timeout)?));
+    }
+    if let Some(timeout) = &config.connect_timeout {
+        push("--connect-timeout", Some(&seconds(timeout)?));
+    }
+    match config.redirects.unwrap_or(DEFAULT_REDIRECTS) {
+        0 => {}
+        redirects => {
+            push("-L", None);
+            push("--max-redirs", Some(&redirects.to_string()));
+        }
+    }
+    if config.keep_alive == Some(false) {
+        push("--no-keepalive", None);
+    }
+    if let Some(user_agent) = &config.user_agent {
+        push("-A", Some(user_agent));
+    }
+    push("--compressed", None);
+    if let Some(tls) = &config.tls {
+        if tls.insecure == Some(true) {
+            push("-k", None);
+        }
+        for (option, value) in [
+            ("--cacert", &tls.ca_file),
+            ("--cert", &tls.cert_file),
+            ("--key", &tls.key_file),
+        ] {
+            if let Some(value) = value {
+                push(option, Some(value));
+            }
+        }
+    }
+    if let Some(proxy) = &config.proxy {
+        push("-x", Some(proxy));
+    }
+    if let Some(no_proxy) = &config.no_proxy {
+        push("--noproxy", Some(&no_proxy.join(",")));
+    }
+    for (host, addr) in config.resolve.iter().flatten() {
+        let host = match host.contains(':') {
+            true => host.clone(),
+            false => {
+                let port = url::Url::parse(&url)?.port_or_known_default().unwrap_or(80);
+                format!("{}:{}", host, port)
+            }
+        };
+        let addr = match addr.contains(':') && !addr.starts_with('[') {
+            true => format!("[{}]", addr),
+            false => addr.clone(),
+        };
+        push("--resolve", Some(&format!("{}:{}", host, addr)));
+    }
+
+    comments.push(args.join(" \\\n  "));
+    Ok(secrets.mask(&comments.join("\n")))
+}
+
+/// `30s` | `500ms` as the seconds curl takes.
+fn seconds(duration: &str) -> Result<String, Box<dyn Error>> {
+    Ok(parse_duration(duration)?.as_secs_f64().to_string())
+}
+
+/// the value as one shell word, single quoted when it has special characters.
+pub fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    match plain {
+        true => value.to_string(),
+        false => format!("'{}'", value.replace('\'', r"'\''")),
+    }
+}
+
+/// curl options read by `parse`: short name, long name and whether a value follows.
+const OPTIONS: [(Option<char>, &str, bool); 44] = [
+    (Some('X'), "request", true),
+    (Some('H'), "header", true),
+    (Some('d'), "data", true),
+    (None, "data-raw", true),
+    (None, "data-binary", true),
+    (None, "data-ascii", true),
+    (None, "data-urlencode", true),
+    (None, "json", true),
+    (Some('F'), "form", true),
+    (None, "form-string", true),
+    (Some('G'), "get", false),
+    (Some('u'), "user", true),
+    (None, "digest", false),
+    (None, "basic", false),
+    (Some('b'), "cookie", true),
+    (Some('A'), "user-agent", true),
+    (Some('e'), "referer", true),
+    (None, "url", true),
+    (Some('o'), "output", true),
+    (Some('m'), "max-time", true),
+    (None, "connect-timeout", true),
+    (None, "max-redirs", true),
+    (Some('L'), "location", false),
+    (None, "no-keepalive", false),
+    (Some('k'), "insecure", false),
+    (None, "cacert", true),
+    (Some('E'), "cert", true),
+    (None, "key", true),
+    (Some('x'), "proxy", true),
+    (None, "noproxy", true),
+    (None, "resolve", true),
+    (None, "unix-socket", true),
+    (None, "aws-sigv4", true),
+    (Some('I'), "head", false),
+    // output and protocol options without a template setting
+    (None, "compressed", false),
+    (Some('s'), "silent", false),
+    (Some('S'), "show-error", false),
+    (Some('v'), "verbose", false),
+    (Some('i'), "include", false),
+    (Some('g'), "globoff", false),
+    (Some('f'), "fail", false),
+    (Some('N'), "no-buffer", false),
+    (None, "http1.1", false),
+    (None, "http2", false),
+];
+
+/// a request from a curl command line, as copied from browser devtools.
+pub fn parse(command: &str) -> Result<Request, Box<dyn Error>> {
+    let mut words = split(command)?.into_iter().peekable();
+    if words.peek().is_some_and(|w| w == "curl") {
+        words.next();
+    }
+    let mut options: Vec<(&str, String)> = Vec::new();
+    let mut urls = Vec::new();
+    while let Some(word) = words.next() {
+        let mut value = |option: &str| {
+            words.next().ok_or_else(|| -> Box<dyn Error> {
+                yurl_error!(&format!("{} needs a value", option))
+            })
+        };
+        if let Some(long) = word.strip_prefix("--") {
+            let (_, name, takes_value) =
+                OPTIONS
+                    .iter()
+                    .find(|o| o.1 == long)
+                    .ok_or_else(|| -> Box<dyn Error> {
+                        yurl_error!(&format!("unsupported curl option: {}", word))
+                    })?;
+            let value = match takes_value {
+                true => value(&word)?,
+                false => String::new(),
+            };
+            options.push((name, value));
+        } else if word.len() > 1 && word.starts_with('-') {
+            // -sSL, -XPOST and -H 'name: value' alike
+            for (i, c) in word.char_indices().skip(1) {
+                let (_, name, takes_value) =
+                    OPTIONS
+                        .iter()
+                        .find(|o| o.0 == Some(c))
+                        .ok_or_else(|| -> Box<dyn Error> {
+                            yurl_error!(&format!("unsupported curl option: -{}", c))
+                        })?;
+                if !takes_value {
+                    options.push((name, String::new()));
+                    continue;
+                }
+                let rest = &word[i + c.len_utf8()..];
+                let value = match rest.is_empty() {
+                    true => value(&word)?,
+                    false => rest.to_string(),
+                };
+                options.push((name, value));
+                break;
+            }
+        } else {
+            urls.push(word);
+        }
+    }
+
+    let mut request = Request {
+        name: String::new(),
+        headers: HashMap::new(),
+        params: serde_yaml::Value::Mapping(Mapping::new()),
+        response_type: ResponseType::TEXT,
+        ..Default::default()
+    };
+    let mut method = None;
+    let mut get = false;
+    let mut digest = false;
+    let mut user = None;
+    let mut data: Vec<String> = Vec::new();
+    let mut json: Vec<String> = Vec::new();
+    let mut form = Mapping::new();
+    for (name, value) in options {
+        match name {
+            "request" => method = Some(value.to_uppercase()),
+            "head" => method = Some("HEAD".to_string()),
+            "header" => match value.split_once(':') {
+                Some((key, value)) if key.eq_ignore_ascii_case("cookie") => {
+                    add_cookies(&mut request, value)
+                }
+                Some((key, value)) => match value.trim().strip_prefix("Bearer ") {
+                    Some(token) if key.eq_ignore_ascii_case("authorization") => {
+                        request.auth = Some(Auth::BEARER {
+                            token: token.trim().to_string(),
+                        })
+                    }
+                    // `name:` removes a header curl sends by default
+                    _ if value.trim().is_empty() => {}
+                    _ => {
+                        request
+                            .headers
+                            .insert(key.trim().to_string(), value.trim().to_string());
+                    }
+                },
+                None => {}
+            },
+            "data" | "data-ascii" => data.push(read_data(&value)?.replace(['\r', '\n'], "")),
+            "data-binary" => data.push(read_data(&value)?),
+            "data-raw" => data.push(value),
+            "data-urlencode" => {
+                let (name, content) = match value.find(['=', '@']) {
+                    Some(i) if value[i..].starts_with('@') => {
+                        (&value[..i], fs::read_to_string(&value[i + 1..])?)
+                    }
+                    Some(i) => (&value[..i], value[i + 1..].to_string()),
+                    None => ("", value.clone()),
+                };
+                let content: String =
+                    url::form_urlencoded::byte_serialize(content.as_bytes()).collect();
+                data.push(match name.is_empty() {
+                    true => content,
+                    false => format!("{}={}", name, content),
+                });
+            }
+            "json" => {
+                json.push(read_data(&value)?);
+                request.response_type = ResponseType::JSON;
+            }
+            "form" => {
+                let (key, value) = form_part(&value)?;
+                insert(&mut form, key, value);
+            }
+            "form-string" => {
+                let (key, value) = value.split_once('=').unwrap_or((&value, ""));
+                insert(&mut form, key.to_string(), value.into());
+            }
+            "get" => get = true,
+            "user" => user = Some(value),
+            "digest" => digest = true,
+            "basic" => digest = false,
+            "cookie" if value.contains('=') => add_cookies(&mut request, &value),
+            "cookie" => {
+                return Err(yurl_error!(&format!(
+                    "cookie files are not supported: {}",
+                    value
+                )))
+            }
+            "user-agent" => request.config.user_agent = Some(value),
+            "referer" => {
+                request.headers.insert("Referer".to_string(), value);
+            }
+            "url" => urls.push(value),
+            "output" => {
+                request.response_type = ResponseType::FILE;
+                request.save_to = Some(value);
+            }
+            "max-time" => request.config.timeout = Some(format!("{}s", value)),
+            "connect-timeout" => request.config.connect_timeout = Some(format!("{}s", value)),
+            "max-redirs" => request.config.redirects = Some(value.parse()?),
+            "no-keepalive" => request.config.keep_alive = Some(false),
+            "insecure" => tls(&mut request).insecure = Some(true),
+            "cacert" => tls(&mut request).ca_file = Some(value),
+            "cert" => tls(&mut request).cert_file = Some(value),
+            "key" => tls(&mut request).key_file = Some(value),
+            "proxy" => request.config.proxy = Some(value),
+            "noproxy" => {
+                request.config.no_proxy =
+                    Some(value.split(',').map(|h| h.trim().to_string()).collect())
+            }
+            "resolve" => {
+                let mut parts = value.splitn(3, ':');
+                let (Some(host), Some(port), Some(addr)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(yurl_error!(&format!(
+                        "expected host:port:addr, got {}",
+                        value
+                    )));
+                };
+                request
+                    .config
+                    .resolve
+                    .get_or_insert_with(Default::default)
+                    .insert(format!("{}:{}", host, port), addr.to_string());
+            }
+            "unix-socket" => request.config.unix_socket = Some(value),
+            "aws-sigv4" => {
+                return Err(yurl_error!(
+                    "--aws-sigv4 is not imported, set sign in the template"
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    request.url = match urls.as_slice() {
+        [url] => url.clone(),
+        [] => return Err(yurl_error!("curl command without url")),
+        _ => return Err(yurl_error!("curl command with more than one url")),
+    };
+    request.name = name_of(&request.url);
+    if let Some(user) = user {
+        let (username, password) = user.split_once(':').unwrap_or((&user, ""));
+        let (username, password) = (username.to_string(), password.to_string());
+        request.auth = Some(match digest {
+            true => Auth::DIGEST { username, password },
+            false => Auth::BASIC { username, password },
+        });
+    }
+    let content_type = request
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(CONTENT_TYPE))
+        .map(|(_, v)| v.to_lowercase())
+        .unwrap_or_default();
+    if request
+        .headers
+        .iter()
+        .any(|(k, v)| k.eq_ignore_ascii_case("accept") && v.contains("json"))
+    {
+        request.response_type = ResponseType::JSON;
+    }
+
+    let body = !data.is_empty() || !json.is_empty() || !form.is_empty();
+    if !form.is_empty() {
+        if !data.is_empty() || !json.is_empty() {
+            return Err(yurl_error!("-F can not be combined with -d or --json"));
+        }
+        request.content_type = ContentType::FILE;
+        request.params = serde_yaml::Value::Mapping(form);
+    } else if !json.is_empty() || (!get && !data.is_empty() && content_type.contains("json")) {
+        // curl sends the pieces one after the other
+        let body = json.concat() + &data.join("&");
+        let body: serde_json::Value = serde_json::from_str(&body)
+            .map_err(|e| -> Box<dyn Error> { yurl_error!(&format!("json body: {}", e)) })?;
+        request.content_type = ContentType::JSON;
+        request.params = serde_yaml::to_value(body)?;
+    } else if !data.is_empty() {
+        if !get && !content_type.is_empty() && !content_type.contains(ContentType::FORM.to_kv().1) {
+            return Err(yurl_error!(&format!(
+                "only form, json and multipart bodies are supported, got {}",
+                content_type
+            )));
+        }
+        let mut params = Mapping::new();
+        for (key, value) in url::form_urlencoded::parse(data.join("&").as_bytes()) {
+            insert(&mut params, key.into_owned(), value.into_owned().into());
+        }
+        request.content_type = match get {
+            true => ContentType::URLENCODED,
+            false => ContentType::FORM,
+        };
+        request.params = serde_yaml::Value::Mapping(params);
+    }
+    // the Content-Type of the body is set by yurl
+    if body {
+        request
+            .headers
+            .retain(|k, _| !k.eq_ignore_ascii_case(CONTENT_TYPE));
+    }
+
+    let method = match method {
+        Some(method) => method,
+        None if body && !get => "POST".to_string(),
+        None => "GET".to_string(),
+    };
+    request.method = serde_yaml::from_value(serde_yaml::Value::String(method.clone())).map_err(
+        |_| -> Box<dyn Error> { yurl_error!(&format!("unsupported method: {}", method)) },
+    )?;
+    Ok(request)
+}
+
+/// split a shell command line into words: '...', "...", $'...' and `\` line continuations.
+fn split(command: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(yurl_error!("unterminated ' in curl command")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(yurl_error!("unterminated \" in curl command")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(yurl_error!("unterminated \" in curl command")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => word.push('\n'),
+                            Some('r') => word.push('\r'),
+                            Some('t') => word.push('\t'),
+                            Some(c @ ('x' | 'u')) => {
+                                let len = if c == 'x' { 2 } else { 4 };
+                                let mut hex = String::new();
+                                while hex.len() < len
+                                    && chars.peek().is_some_and(|c| c.is_ascii_hexdigit())
+                                {
+                                    hex.extend(chars.next());
+                                }
+                                let code =
+                                    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                                word.push(code.ok_or_else(|| -> Box<dyn Error> {
+                                    yurl_error!(&format!("invalid escape \\{}{}", c, hex))
+                                })?);
+                            }
+                            Some(c) => word.push(c),
+                            None => return Err(yurl_error!("unterminated $' in curl command")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(yurl_error!("unterminated $' in curl command")),
+                    }
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// `-d @file` reads the body from the file, `-` is stdin and not supported.
+fn read_data(value: &str) -> Result<String, Box<dyn Error>> {
+    match value.strip_prefix('@') {
+        Some(path) => Ok(fs::read_to_string(path)?),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// a `-F` value in double quotes, so `;`, `@` and `<` are kept as text.
+fn form_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// name and param of `-F name=@path;type=..;filename=..`, `name=<path` or `name=text`.
+fn form_part(value: &str) -> Result<(String, serde_yaml::Value), Box<dyn Error>> {
+    let (key, value) = value.split_once('=').ok_or_else(|| -> Box<dyn Error> {
+        yurl_error!(&format!("-F expects name=content, got {}", value))
+    })?;
+    let mut attributes = value.split(';');
+    let content = attributes.next().unwrap_or_default();
+    let mut part = Mapping::new();
+    match (content.strip_prefix('@'), content.strip_prefix('<')) {
+        (Some(path), _) => part.insert("file".into(), path.into()),
+        (_, Some(path)) => part.insert("text".into(), fs::read_to_string(path)?.into()),
+        _ => part.insert("text".into(), content.into()),
+    };
+    for attribute in attributes {
+        match attribute.trim().split_once('=') {
+            Some(("type", v)) => part.insert("content_type".into(), v.into()),
+            Some(("filename", v)) => part.insert("filename".into(), v.trim_matches('"').into()),
+            _ => None,
+        };
+    }
+    let value = match (part.len(), part.get("file"), part.get("text")) {
+        (1, Some(file), _) => format!("FILE({})", file.as_str().unwrap_or_default()).into(),
+        (1, _, Some(text)) => text.clone(),
+        _ => serde_yaml::Value::Mapping(part),
+    };
+    Ok((key.to_string(), value))
+}
+
+/// repeated names become a list, sent as `a=1&a=2` by ArrayFormat::REPEAT.
+fn insert(params: &mut Mapping, key: String, value: serde_yaml::Value) {
+    let key = serde_yaml::Value::String(key);
+    match params.get_mut(&key) {
+        Some(serde_yaml::Value::Sequence(values)) => values.push(value),
+        Some(first) => *first = serde_yaml::Value::Sequence(vec![first.clone(), value]),
+        None => {
+            params.insert(key, value);
+        }
+    }
+}
+
+fn add_cookies(request: &mut Request, cookies: &str) {
+    for cookie in cookies.split(';') {
+        if let Some((name, value)) = cookie.split_once('=') {
+            request
+                .cookies
+                .insert(name.trim().to_string(), value.trim().to_string());
+        }
+    }
+}
+
+fn tls(request: &mut Request) -> &mut Tls {
+    request.config.tls.get_or_insert_with(Default::default)
+}
+
+/// request name from the last path segment of the url, `request` without one.
+fn name_of(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let segment = path
+        .split('/')
+        .skip(1)
+        .filter(|s| !s.is_empty())
+        .last()
+        .unwrap_or_default();
+    let name: String = segment
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+            true => c.to_ascii_lowercase(),
+            false => '_',
+        })
+        .collect();
+    match name.trim_matches('_') {
+        "" => "request".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{command, parse, quote};
+    use crate::core::auth::Auth;
+    use crate::core::client::Defaults;
+    use crate::core::request::{ContentType, Method, Request, ResponseType};
+    use crate::core::secret::Secrets;
+    use crate::core::sign::Sign;
+
+    #[test]
+    fn test_command() {
+        assert_eq!(quote("it's a b"), r"'it'\''s a b'");
+        let request = Request {
+            method: Method::POST,
+            url: "http://localhost:8080/users?x=1".to_string(),
+            headers: HashMap::from([("X-Id".to_string(), "a b".to_string())]),
+            params: serde_yaml::from_str::<serde_yaml::Value>("{name: tom, avatar: FILE(./a.png)}")
+                .unwrap(),
+            content_type: ContentType::FILE,
+            auth: Some(Auth::BASIC {
+                username: "tom".to_string(),
+                password: "p'w".to_string(),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            command(&request, &Defaults::default(), &Secrets::default()).unwrap(),
+            "curl \\\n  -X POST \\\n  'http://localhost:8080/users?x=1' \\\n  -H 'X-Id: a b' \\\n  \
+             -u 'tom:p'\\''w' \\\n  --form-string name=tom \\\n  -F avatar=@./a.png \\\n  \
+             -L \\\n  --max-redirs 5 \\\n  --compressed"
+        );
+        let request = Request {
+            url: "http://localhost/search".to_string(),
+            headers: HashMap::new(),
+            params: serde_yaml::from_str::<serde_yaml::Value>("{q: a&b}").unwrap(),
+            content_type: ContentType::URLENCODED,
+            ..Default::default()
+        };
+        assert!(command(&request, &Defaults::default(), &Secrets::default())
+            .unwrap()
+            .starts_with("curl \\\n  'http://localhost/search?q=a%26b' \\\n"));
+        let request = Request {
+            method: Method::PUT,
+            url: "http://localhost/upload".to_string(),
+            headers: HashMap::new(),
+            params: serde_yaml::from_str::<serde_yaml::Value>(
+                r#"{a: {text: "@x;y"}, b: {text: "<t", filename: "a;b.txt"}, c: {json: {k: "v;\"w"}}}"#,
+            )
+            .unwrap(),
+            content_type: ContentType::FILE,
+            sign: Some(Sign::AWS4 {
+                access_key: "AKID".to_string(),
+                secret_key: "sk-1".to_string(),
+                session_token: Some("tok-1".to_string()),
+                region: "us-east-1".to_string(),
+                service: "s3".to_string(),
+            }),
+            ..Default::default()
+        };
+        let aws = command(&request, &Defaults::default(), &Secrets::default()).unwrap();
+        assert!(!aws.contains("sk-1") && !aws.contains("tok-1"), "{aws}");
+        for arg in [
+            "--user AKID:\"$AWS_SECRET_ACCESS_KEY\"",
+            "-H \"x-amz-security-token: $AWS_SESSION_TOKEN\"",
+            "--form-string 'a=@x;y'",
+            r#"-F 'b="<t";filename="a;b.txt"'"#,
+            r#"-F 'c="{\"k\":\"v;\\\"w\"}";type=application/json'"#,
+        ] {
+            assert!(aws.contains(arg), "{arg} not in {aws}");
+        }
+
+        let request = Request {
+            url: "http://localhost/me".to_string(),
+            headers: [
+                ("Authorization", "Bearer literal-secret"),
+                ("Cookie", "sid=cookie-secret"),
+                ("X-Key", "var-secret"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            cookies: [("sid2".to_string(), "jar-secret".to_string())].into(),
+            params: serde_yaml::Value::Null,
+            ..Default::default()
+        };
+        let mut secrets = Secrets::default();
+        secrets.add("var-secret");
+        let masked = command(&request, &Defaults::default(), &secrets).unwrap();
+        for secret in [
+            "literal-secret",
+            "cookie-secret",
+            "var-secret",
+            "jar-secret",
+        ] {
+            assert!(!masked.contains(secret), "{secret} in {masked}");
+        }
+        assert!(masked.contains("-b 'sid2=***'"), "{masked}");
+    }
+
+    #[test]
+    fn test_parse() {
+        let request = parse(
+            r#"curl 'https://api.example.com/v1/users?page=2' \
+  -H 'accept: application/json' -H 'content-type: application/json' \
+  -H $'x-note: it\'s' -b 'sid=abc; theme=dark' \
+  --data-raw '{"name":"tom"}' --compressed"#,
+        )
+        .unwrap();
+        assert_eq!(request.name, "users");
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.content_type, ContentType::JSON);
+        assert_eq!(request.response_type, ResponseType::JSON);
+        assert_eq!(request.headers["x-note"], "it's");
+        assert!(!request.headers.contains_key("content-type"));
+        assert_eq!(request.cookies["theme"], "dark");
+        assert_eq!(
+            request.params,
+            serde_yaml::from_str::<serde_yaml::Value>("{name: tom}").unwrap()
+        );
+
+        let request =
+            parse("curl -sSG http://localhost/search -d q=a%26b -d q=c -u tom:pw").unwrap();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.content_type, ContentType::URLENCODED);
+        assert_eq!(
+            request.params,
+            serde_yaml::from_str::<serde_yaml::Value>("{q: [a&b, c]}").unwrap()
+        );
+        assert!(matches!(request.auth, Some(Auth::BASIC { .. })));
+        // the exported command sends the params in the url
+        let exported =
+            parse(&command(&request, &Defaults::default(), &Secrets::default()).unwrap()).unwrap();
+        assert_eq!(exported.url, "http://localhost/search?q=a%26b&q=c");
+        assert_eq!(exported.auth, request.auth);
+
+        let request = parse(
+            "curl -X PUT http://localhost/upload -F a=@./a.png -F 'b=@./b.txt;type=text/plain'",
+        )
+        .unwrap();
+        assert_eq!(request.content_type, ContentType::FILE);
+        assert_eq!(
+            request.params,
+            serde_yaml::from_str::<serde_yaml::Value>(
+                "{a: FILE(./a.png), b: {file: ./b.txt, content_type: text/plain}}"
+            )
+            .unwrap()
+        );
+        assert!(parse("curl -I http://localhost").is_err());
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::client::Defaults;
+use crate::core::expression::Expression;
+use crate::core::function::Function;
+use crate::core::multipart::Part;
+use crate::core::request::{ContentType, Request};
+use crate::core::yaml;
+
+/// a template problem found by `yurl check`, line is 1 based, 0 when unknown.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.message)
+    }
+}
+
+/// a template file and its yaml.
+struct Source {
+    file: String,
+    text: String,
+    value: serde_yaml::Value,
+}
+
+impl Source {
+    /// first line from the line `from` containing the text.
+    fn line_of(&self, text: &str, from: usize) -> usize {
+        self.text
+            .lines()
+            .enumerate()
+            .skip(from.saturating_sub(1))
+            .find(|(_, line)| line.contains(text))
+            .map_or(from, |(i, _)| i + 1)
+    }
+
+    fn problem(&self, line: usize, message: String) -> Problem {
+        Problem {
+            file: self.file.clone(),
+            line,
+            message,
+        }
+    }
+}
+
+/// a request of a template, in run order once sorted.
+struct Entry<'a> {
+    source: &'a Source,
+    line: usize,
+    order: i64,
+    name: String,
+    value: &'a serde_yaml::Value,
+    request: Option<Request>,
+}
+
+/// check the template and its imports without sending anything, every problem is returned.
+pub fn check(file: &str) -> (Vec<Problem>, usize) {
+    let mut problems = Vec::new();
+    let mut sources = Vec::new();
+    load(
+        Path::new(file),
+        &mut HashSet::new(),
+        &mut sources,
+        &mut problems,
+    );
+    let variables: Vec<serde_yaml::Value> = sources
+        .iter()
+        .filter_map(|s| s.value.get("vars"))
+        .filter(|v| !v.is_null())
+        .cloned()
+        .collect();
+    let functions = Function::functions();
+
+    let mut entries: Vec<Entry> = Vec::new();
+    for source in &sources {
+        if let Some(defaults) = source.value.get("defaults") {
+            let line = source.line_of("defaults:", 1);
+            if let Err(e) = serde_yaml::from_value::<Defaults>(defaults.clone()) {
+                problems.push(source.problem(line, format!("defaults: {}", e)));
+            }
+            let mut strings = Vec::new();
+            collect_strings(defaults, &mut strings);
+            for text in strings {
+                for expression in Expression::parse_from_str(text).unwrap_or_default() {
+                    let line = source.line_of(&expression, line);
+                    let message = match Expression::parse(&expression) {
+                        Ok(Expression::Response(_)) => Some(format!(
+                            "{}: defaults can not use response expressions",
+                            expression
+                        )),
+                        _ => check_expression(&expression, &variables, &functions),
+                    };
+                    if let Some(message) = message {
+                        problems.push(source.problem(line, message));
+                    }
+                }
+            }
+        }
+        let Some(requests) = source.value.get("requests") else {
+            continue;
+        };
+        let Some(requests) = requests.as_sequence() else {
+            let line = source.line_of("requests:", 1);
+            problems.push(source.problem(line, "requests must be a list".to_string()));
+            continue;
+        };
+        let mut from = source.line_of("requests:", 1);
+        for value in requests {
+            let name = value
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let line = match name.is_empty() {
+                true => from,
+                false => source.line_of(&format!("name: {}", name), from),
+            };
+            from = line;
+            let request = match serde_yaml::from_value::<Request>(value.clone()) {
+                Ok(request) => Some(request),
+                Err(e) => {
+                    problems.push(source.problem(line, format!("request [{}]: {}", name, e)));
+                    None
+                }
+            };
+            entries.push(Entry {
+                source,
+                line,
+                order: value.get("order").and_then(|o| o.as_i64()).unwrap_or(0),
+                name,
+                value,
+                request,
+            });
+        }
+    }
+    // the run order, see Template::from_to_file
+    entries.sort_by_key(|e| e.order);
+
+    for (i, entry) in entries.iter().enumerate() {
+        if entries[..i].iter().any(|e| e.name == entry.name) {
+            problems.push(
+                entry
+                    .source
+                    .problem(entry.line, format!("duplicated request: {}", entry.name)),
+            );
+        }
+        let mut strings = Vec::new();
+        collect_strings(entry.value, &mut strings);
+        for text in strings {
+            for expression in Expression::parse_from_str(text).unwrap_or_default() {
+                let message = match Expression::parse(&expression) {
+                    Ok(Expression::Response(expr)) => {
+                        let parent = Expression::response_parse(&expr).map(|r| r.parent);
+                        match parent {
+                            Err(e) => Some(format!("{}: {}", expression, e)),
+                            Ok(parent) => match entries.iter().position(|e| e.name == parent) {
+                                None => Some(format!(
+                                    "{}: request [{}] does not exist",
+                                    expression, parent
+                                )),
+                                Some(j) if j >= i => Some(format!(
+                                    "{}: request [{}] runs after [{}]",
+                                    expression, parent, entry.name
+                                )),
+                                Some(_) => None,
+                            },
+                        }
+                    }
+                    _ => check_expression(&expression, &variables, &functions),
+                };
+                if let Some(message) = message {
+                    let line = entry.source.line_of(&expression, entry.line);
+                    problems.push(entry.source.problem(line, message));
+                }
+            }
+        }
+        if let Some(request) = &entry.request {
+            if request.content_type == ContentType::FILE {
+                let mut paths = Vec::new();
+                collect_files(&request.params, &mut paths);
+                for path in paths {
+                    // expressions are only known at run time, paths are relative to the working directory
+                    if !path.contains("${") && !Path::new(&path).exists() {
+                        let line = entry.source.line_of(&path, entry.line);
+                        problems.push(
+                            entry
+                                .source
+                                .problem(line, format!("file not found: {}", path)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    let count = entries.len();
+    (problems, count)
+}
+
+/// read the file and its imports, imports first like Template::from_to_file.
+fn load(
+    path: &Path,
+    loaded: &mut HashSet<PathBuf>,
+    sources: &mut Vec<Source>,
+    problems: &mut Vec<Problem>,
+) {
+    let file = path.display().to_string();
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            problems.push(Problem {
+                file,
+                line: 0,
+                message: format!("read file: {}", e),
+            });
+            return;
+        }
+    };
+    if !loaded.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf())) {
+        return;
+    }
+    let value: serde_yaml::Value = match serde_yaml::from_str(&text) {
+        Ok(value) => value,
+        Err(e) => {
+            problems.push(Problem {
+                file,
+                line: e.location().map_or(0, |l| l.line()),
+                message: e.to_string(),
+            });
+            return;
+        }
+    };
+    let source = Source { file, text, value };
+    let parent = path.parent().unwrap_or(Path::new(""));
+    if let Some(imports) = source.value.get("imports") {
+        for import in imports.as_sequence().into_iter().flatten() {
+            let Some(import) = import.as_str() else {
+                let line = source.line_of("imports:", 1);
+                problems.push(source.problem(line, "imports must be file paths".to_string()));
+                continue;
+            };
+            let import_path = parent.join(import);
+            if !import_path.is_file() {
+                let line = source.line_of(import, 1);
+                problems.push(source.problem(line, format!("import not found: {}", import)));
+                continue;
+            }
+            load(&import_path, loaded, sources, problems);
+        }
+    }
+    sources.push(source);
+}
+
+/// None when the variable or function expression can be resolved, or the problem.
+fn check_expression(
+    expression: &str,
+    variables: &[serde_yaml::Value],
+    functions: &std::collections::HashMap<String, Function>,
+) -> Option<String> {
+    match Expression::parse(expression) {
+        Err(e) => Some(e.to_string()),
+        Ok(Expression::Variable(expr)) => match Expression::variable_parse(&expr) {
+            Err(e) => Some(format!("{}: {}", expression, e)),
+            Ok(key) => match yaml::variable(variables, &key) {
+                Ok(Some(_)) => None,
+                Ok(None) => Some(format!("undefined variable: {}", key)),
+                Err(e) => Some(e.to_string()),
+            },
+        },
+        Ok(Expression::Function(expr)) => match Expression::function_parse(&expr) {
+            Err(e) => Some(format!("{}: {}", expression, e)),
+            Ok(key) => {
+                (!functions.contains_key(&key)).then(|| format!("undefined function: {}", key))
+            }
+        },
+        Ok(Expression::Response(_)) => None,
+    }
+}
+
+fn collect_strings<'a>(value: &'a serde_yaml::Value, strings: &mut Vec<&'a str>) {
+    match value {
+        serde_yaml::Value::String(v) => strings.push(v),
+        serde_yaml::Value::Sequence(v) => v.iter().for_each(|v| collect_strings(v, strings)),
+        serde_yaml::Value::Mapping(v) => v.values().for_each(|v| collect_strings(v, strings)),
+        serde_yaml::Value::Tagged(v) => collect_strings(&v.value, strings),
+        _ => {}
+    }
+}
+
+/// paths of `FILE(path)` params and `file:` parts.
+fn collect_files(value: &serde_yaml::Value, paths: &mut Vec<String>) {
+    match value {
+        serde_yaml::Value::String(v) if v.starts_with("FILE(") && v.ends_with(')') => {
+            paths.push(v[5..v.len() - 1].to_string())
+        }
+        serde_yaml::Value::Sequence(v) => v.iter().for_each(|v| collect_files(v, paths)),
+        serde_yaml::Value::Mapping(v) if Part::is_part(value) => {
+            if let Some(file) = v.get("file").and_then(|f| f.as_str()) {
+                paths.push(file.to_string());
+            }
+        }
+        serde_yaml::Value::Mapping(v) => v.values().for_each(|v| collect_files(v, paths)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::check;
+
+    #[test]
+    fn test_check() {
+        let dir = std::env::temp_dir().join("yurl-lint-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("vars.yaml"), "vars:\n  host: localhost\n").unwrap();
+        let template = r#"imports:
+  - ./vars.yaml
+  - ./missing.yaml
+requests:
+  - order: 1
+    name: login
+    url: http://${var.host}/${res.user.id}
+    method: GET
+    headers: {}
+    params: {}
+    content_type: URLENCODED
+    response_type: JSON
+  - order: 2
+    name: user
+    url: http://${var.port}/${fun.nope}/${env.HOME}
+    method: GETT
+    headers: {}
+    params:
+      avatar: FILE(./no-such-file.png)
+    content_type: FILE
+    response_type: JSON
+  - order: 3
+    name: login
+    url: http://${var.host}/${res.login.id}
+    method: GET
+    headers: {}
+    params: {}
+    content_type: URLENCODED
+    response_type: JSON
+"#;
+        let file = dir.join("template.yaml");
+        fs::write(&file, template).unwrap();
+        let (problems, count) = check(file.to_str().unwrap());
+        let problems: Vec<(usize, String)> =
+            problems.into_iter().map(|p| (p.line, p.message)).collect();
+        assert_eq!(count, 3);
+        assert_eq!(
+            problems[0],
+            (3, "import not found: ./missing.yaml".to_string())
+        );
+        assert_eq!(problems[1].0, 14);
+        assert!(problems[1]
+            .1
+            .starts_with("request [user]: unknown variant `GETT`"));
+        assert_eq!(
+            problems[2..],
+            [
+                (
+                    7,
+                    "${res.user.id}: request [user] runs after [login]".to_string()
+                ),
+                (15, "undefined variable: port".to_string()),
+                (15, "undefined function: nope".to_string()),
+                (15, "not supported expression type: env".to_string()),
+                (23, "duplicated request: login".to_string()),
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_variables() {
+        let dir = std::env::temp_dir().join("yurl-lint-vars-test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.yaml"), "vars:\n  host: localhost\n  port: 80\n").unwrap();
+        fs::write(dir.join("b.yaml"), "vars:\n  host: example.com\n").unwrap();
+        let template = r#"imports:
+  - ./a.yaml
+  - ./b.yaml
+requests:
+  - order: 1
+    name: login
+    url: http://${var.host}:${var.port}/
+    method: GET
+    headers: {}
+    params: {}
+    content_type: URLENCODED
+    response_type: JSON
+"#;
+        let file = dir.join("template.yaml");
+        fs::write(&file, template).unwrap();
+        let (problems, count) = check(file.to_str().unwrap());
+        let problems: Vec<(usize, String)> =
+            problems.into_iter().map(|p| (p.line, p.message)).collect();
+        assert_eq!(count, 1);
+        assert_eq!(problems, [(7, "undefined variable: port".to_string())]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,10 +5,19 @@ macro_rules! success {
     };
 }
 
+/// errors go to stderr, so machine readable output on stdout stays valid
 #[macro_export]
 macro_rules! error {
     ($e:expr) => {
-        println!("{}", $e.red())
+        eprintln!("{}", $e.red())
+    };
+}
+
+/// progress messages, printed to stderr
+#[macro_export]
+macro_rules! info {
+    ($e:expr) => {
+        eprintln!("{}", $e.green())
     };
 }
 
@@ -1,11 +1,15 @@
+use base64::Engine;
 use mime::Mime;
 use rand::Rng;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::error::Error;
 use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use ureq::{Error, Request, Response};
+use ureq::{Request, Response};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 const BOUNDARY_LEN: usize = 29;
 
@@ -27,11 +31,64 @@ fn mime_filename(path: &Path) -> (Mime, Option<&str>) {
     (content_type.first_or_octet_stream(), filename)
 }
 
+const PART_BODY_KEYS: [&str; 4] = ["file", "text", "base64", "json"];
+const PART_OPTION_KEYS: [&str; 2] = ["filename", "content_type"];
+
+/// multipart part described by a mapping in FILE params
+///
+/// exactly one of `file`, `text`, `base64` or `json` is the part body,
+/// `filename` and `content_type` override the guessed values.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Part {
+    pub file: Option<String>,
+    pub text: Option<String>,
+    pub base64: Option<String>,
+    pub json: Option<serde_json::Value>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl Part {
+    /// whether the yaml value is a part mapping rather than a nested object
+    pub fn is_part(value: &serde_yaml::Value) -> bool {
+        match value.as_mapping() {
+            Some(m) => {
+                let keys: Vec<&str> = m.keys().filter_map(|k| k.as_str()).collect();
+                keys.len() == m.len()
+                    && keys.iter().filter(|k| PART_BODY_KEYS.contains(k)).count() == 1
+                    && keys
+                        .iter()
+                        .all(|k| PART_BODY_KEYS.contains(k) || PART_OPTION_KEYS.contains(k))
+            }
+            None => false,
+        }
+    }
+
+    fn content_type(&self) -> io::Result<Option<Mime>> {
+        match &self.content_type {
+            Some(content_type) => content_type
+                .parse::<Mime>()
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// a piece of the multipart body, files are only opened when they are read
+#[derive(Debug)]
+enum Segment {
+    Bytes(Vec<u8>),
+    File(PathBuf),
+}
+
 /// multipart data build
 #[derive(Debug)]
 pub struct MultipartBuilder {
     boundary: String,
     inner: Vec<u8>,
+    segments: Vec<Segment>,
     data_written: bool,
 }
 impl Default for MultipartBuilder {
@@ -45,6 +102,7 @@ impl MultipartBuilder {
         Self {
             boundary: random_alphanumeric(BOUNDARY_LEN),
             inner: Vec::new(),
+            segments: Vec::new(),
             data_written: false,
         }
     }
@@ -64,8 +122,71 @@ impl MultipartBuilder {
     pub fn add_file<P: AsRef<Path>>(self, name: &str, path: P) -> io::Result<Self> {
         let path = path.as_ref();
         let (content_type, filename) = mime_filename(path);
-        let mut file = File::open(path)?;
-        self.add_stream(&mut file, name, filename, Some(content_type))
+        self.add_file_with(name, path, filename, Some(content_type))
+    }
+    /// add file, the file content is streamed when the body is read
+    fn add_file_with(
+        mut self,
+        name: &str,
+        path: &Path,
+        filename: Option<&str>,
+        content_type: Option<Mime>,
+    ) -> io::Result<Self> {
+        if !path.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("file not found: {}", path.display()),
+            ));
+        }
+        let content_type = Some(content_type.unwrap_or(mime::APPLICATION_OCTET_STREAM));
+        self.write_field_headers(name, filename, content_type)?;
+        self.segments
+            .push(Segment::Bytes(std::mem::take(&mut self.inner)));
+        self.segments.push(Segment::File(path.to_path_buf()));
+        Ok(self)
+    }
+    /// add part
+    ///
+    /// * name part field name
+    /// * part the part body and its overrides
+    pub fn add_part(self, name: &str, part: &Part) -> io::Result<Self> {
+        let content_type = part.content_type()?;
+        if let Some(path) = &part.file {
+            let path = Path::new(path);
+            let (guessed, filename) = mime_filename(path);
+            let filename = part.filename.as_deref().or(filename);
+            return self.add_file_with(name, path, filename, Some(content_type.unwrap_or(guessed)));
+        }
+        let (data, default_type) = if let Some(text) = &part.text {
+            if part.filename.is_none() && content_type.is_none() {
+                return self.add_text(name, text);
+            }
+            (text.as_bytes().to_vec(), mime::TEXT_PLAIN_UTF_8)
+        } else if let Some(data) = &part.base64 {
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            (data, mime::APPLICATION_OCTET_STREAM)
+        } else if let Some(json) = &part.json {
+            (serde_json::to_vec(json)?, mime::APPLICATION_JSON)
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("part {name} has no body"),
+            ));
+        };
+        let guessed = part
+            .filename
+            .as_ref()
+            .and_then(|f| mime_guess::from_path(f).first())
+            .filter(|_| part.json.is_none());
+        let content_type = content_type.or(guessed).unwrap_or(default_type);
+        self.add_stream(
+            &mut data.as_slice(),
+            name,
+            part.filename.as_deref(),
+            Some(content_type),
+        )
     }
     /// add some stream
     pub fn add_stream<S: Read>(
@@ -114,14 +235,14 @@ impl MultipartBuilder {
         }
         self.inner.write_all(b"\r\n\r\n")
     }
-    /// general multipart data
+    /// general streaming multipart body, files are read lazily
     ///
     /// # Return
-    /// * (content_type,post_data)
+    /// * (content_type,body)
     ///    * content_type http header content type
-    ///    * post_data ureq.req.send_send_bytes(&post_data)
+    ///    * body ureq.req.send(body), see MultipartBody::content_length
     ///
-    pub fn finish(mut self) -> io::Result<(String, Vec<u8>)> {
+    pub fn finish_stream(mut self) -> io::Result<(String, MultipartBody)> {
         if self.data_written {
             self.inner.write_all(b"\r\n")?;
         }
@@ -132,25 +253,90 @@ impl MultipartBuilder {
             "-----------------------------{}--\r\n",
             self.boundary
         )?;
+        self.segments.push(Segment::Bytes(self.inner));
         Ok((
             format!(
                 "multipart/form-data; boundary=---------------------------{}",
                 self.boundary
             ),
-            self.inner,
+            MultipartBody::new(self.segments),
         ))
     }
 }
 
+/// streaming multipart body, chains the boundaries and file handles
+pub struct MultipartBody {
+    segments: VecDeque<Segment>,
+    current: Option<Box<dyn Read + Send>>,
+    length: Option<u64>,
+}
+
+impl MultipartBody {
+    fn new(segments: Vec<Segment>) -> Self {
+        let length = segments
+            .iter()
+            .map(|s| match s {
+                Segment::Bytes(b) => Some(b.len() as u64),
+                Segment::File(p) => p.metadata().ok().map(|m| m.len()),
+            })
+            .sum();
+        Self {
+            segments: segments.into(),
+            current: None,
+            length,
+        }
+    }
+    /// body length, None when a file size is unknown
+    pub fn content_length(&self) -> Option<u64> {
+        self.length
+    }
+    /// send the body with ureq, with Content-Length when known, otherwise chunked
+    pub fn send(
+        self,
+        mut request: Request,
+        content_type: &str,
+    ) -> Result<Response, Box<dyn Error>> {
+        request = request.set("Content-Type", content_type);
+        if let Some(length) = self.content_length() {
+            request = request.set("Content-Length", &length.to_string());
+        }
+        Ok(request.send(self)?)
+    }
+}
+
+impl Read for MultipartBody {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(current) = self.current.as_mut() {
+                let n = current.read(buf)?;
+                if n > 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+                self.current = None;
+            }
+            match self.segments.pop_front() {
+                Some(Segment::Bytes(b)) => self.current = Some(Box::new(io::Cursor::new(b))),
+                Some(Segment::File(p)) => self.current = Some(Box::new(File::open(p)?)),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
 /// multipart request for ureq
-/// add send_multipart_file/send_multipart_files method to ureq Request
+/// add send_multipart_file/send_multipart_files method to ureq Request,
+/// the files are streamed by MultipartBody
 pub trait MultipartRequest {
-    fn send_multipart_files<P: AsRef<Path>>(self, files: &[P]) -> Result<Response, Error>;
-    fn send_multipart_file<P: AsRef<Path>>(self, name: &str, file: P) -> Result<Response, Error>;
+    fn send_multipart_files<P: AsRef<Path>>(self, files: &[P]) -> Result<Response, Box<dyn Error>>;
+    fn send_multipart_file<P: AsRef<Path>>(
+        self,
+        name: &str,
+        file: P,
+    ) -> Result<Response, Box<dyn Error>>;
 }
 impl MultipartRequest for Request {
     /// send multi files,auto set the name with file's name by multipart
-    fn send_multipart_files<P: AsRef<Path>>(self, files: &[P]) -> Result<Response, Error> {
+    fn send_multipart_files<P: AsRef<Path>>(self, files: &[P]) -> Result<Response, Box<dyn Error>> {
         let mut builder = MultipartBuilder::new();
         for file_path in files {
             let file_path = file_path.as_ref();
@@ -161,12 +347,114 @@ impl MultipartRequest for Request {
                 .unwrap_or_default();
             builder = builder.add_file(file_name, file_path)?;
         }
-        let (content_type, data) = builder.finish()?;
-        self.set("Content-Type", &content_type).send_bytes(&data)
+        let (content_type, body) = builder.finish_stream()?;
+        body.send(self, &content_type)
     }
     /// send single file with name by multipart
-    fn send_multipart_file<P: AsRef<Path>>(self, name: &str, path: P) -> Result<Response, Error> {
-        let (content_type, data) = MultipartBuilder::new().add_file(name, path)?.finish()?;
-        self.set("Content-Type", &content_type).send_bytes(&data)
+    fn send_multipart_file<P: AsRef<Path>>(
+        self,
+        name: &str,
+        path: P,
+    ) -> Result<Response, Box<dyn Error>> {
+        let (content_type, body) = MultipartBuilder::new()
+            .add_file(name, path)?
+            .finish_stream()?;
+        body.send(self, &content_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::{MultipartBuilder, MultipartRequest, Part};
+
+    #[test]
+    fn test_add_part() {
+        let value: serde_yaml::Value = serde_yaml::from_str(
+            r#"
+- text: hello
+  filename: hello.txt
+- base64: aGVsbG8=
+  filename: hello.bin
+  content_type: image/png
+- json:
+    name: tom
+- name: tom
+"#,
+        )
+        .unwrap();
+        let values = value.as_sequence().unwrap();
+        assert!(values[..3].iter().all(Part::is_part));
+        assert!(!Part::is_part(&values[3]));
+        let mut builder = MultipartBuilder::new();
+        for v in &values[..3] {
+            let part: Part = serde_yaml::from_value(v.clone()).unwrap();
+            builder = builder.add_part("part", &part).unwrap();
+        }
+        let (_, mut data) = builder.finish_stream().unwrap();
+        let mut body = String::new();
+        data.read_to_string(&mut body).unwrap();
+        assert!(body.contains(
+            "name=\"part\"; filename=\"hello.txt\"\r\nContent-Type: text/plain\r\n\r\nhello"
+        ));
+        assert!(body.contains(
+            "name=\"part\"; filename=\"hello.bin\"\r\nContent-Type: image/png\r\n\r\nhello"
+        ));
+        assert!(body.contains("Content-Type: application/json\r\n\r\n{\"name\":\"tom\"}"));
+    }
+
+    #[test]
+    fn test_finish_stream() {
+        let path = std::env::temp_dir().join("yurl_test_finish_stream.txt");
+        std::fs::write(&path, "file content").unwrap();
+        let (_, mut body) = MultipartBuilder::new()
+            .add_text("name", "tom")
+            .unwrap()
+            .add_file("file", &path)
+            .unwrap()
+            .finish_stream()
+            .unwrap();
+        let length = body.content_length().unwrap();
+        let mut data = String::new();
+        body.read_to_string(&mut data).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(length, data.len() as u64);
+        assert!(data.contains("filename=\"yurl_test_finish_stream.txt\""));
+        assert!(data.contains("Content-Type: text/plain\r\n\r\nfile content\r\n"));
+    }
+
+    #[test]
+    fn test_send_multipart_file() {
+        use std::io::{BufRead, BufReader, Write};
+        let path = std::env::temp_dir().join("yurl_test_send_multipart.txt");
+        std::fs::write(&path, "file content").unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/upload", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            while reader.read_line(&mut head).unwrap() > 2 && !head.ends_with("\r\n\r\n") {}
+            let length: usize = head
+                .lines()
+                .find_map(|l| l.strip_prefix("Content-Length: "))
+                .unwrap()
+                .parse()
+                .unwrap();
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            (head, String::from_utf8(body).unwrap())
+        });
+        let response = ureq::post(&url).send_multipart_file("file", &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(response.status(), 200);
+        let (head, body) = server.join().unwrap();
+        assert!(head.contains("Content-Type: multipart/form-data; boundary="));
+        assert!(body.contains("name=\"file\"; filename=\"yurl_test_send_multipart.txt\""));
+        assert!(body.contains("\r\n\r\nfile content\r\n"));
     }
 }
@@ -0,0 +1,272 @@
+use std::fmt::Write;
+
+use colored::Colorize;
+
+use crate::core::report::Record;
+
+const INDENT: &str = "  ";
+/// bytes of a binary body shown by the hex dump.
+const HEX_DUMP_LIMIT: usize = 4096;
+/// html elements without a closing tag.
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+/// html elements whose content is kept as is.
+const RAW_ELEMENTS: [&str; 4] = ["script", "style", "pre", "textarea"];
+
+/// whether a body of the content type is not text, json, xml or a form.
+pub fn is_binary(content_type: Option<&str>, bytes: &[u8]) -> bool {
+    let text = content_type.is_some_and(|c| {
+        let c = c.to_lowercase();
+        [
+            "text/",
+            "json",
+            "xml",
+            "javascript",
+            "yaml",
+            "csv",
+            "x-www-form-urlencoded",
+        ]
+        .iter()
+        .any(|t| c.contains(t))
+    });
+    !text && (bytes.contains(&0) || std::str::from_utf8(bytes).is_err())
+}
+
+/// response body of the record rendered by its Content-Type: indented and
+/// colored json, indented xml and html, a hex dump of binary bodies.
+pub fn response_body(record: &Record) -> String {
+    if let Some(bytes) = &record.response_bytes {
+        return hex_dump(bytes);
+    }
+    let content_type = record
+        .response_headers
+        .get("content-type")
+        .map(|c| c.to_lowercase())
+        .unwrap_or_default();
+    match &record.response_body {
+        None => String::new(),
+        Some(serde_json::Value::String(text)) => {
+            if content_type.contains("json") {
+                // not valid json, printed as received
+                if let Ok(value) = serde_json::from_str(text) {
+                    return json(&value);
+                }
+            }
+            if content_type.contains("xml") || content_type.contains("html") {
+                return markup(text, content_type.contains("html"));
+            }
+            text.clone()
+        }
+        Some(value) => json(value),
+    }
+}
+
+pub fn json(value: &serde_json::Value) -> String {
+    let mut out = String::new();
+    write_json(&mut out, value, 0);
+    out
+}
+
+fn write_json(out: &mut String, value: &serde_json::Value, depth: usize) {
+    let indent = INDENT.repeat(depth + 1);
+    match value {
+        serde_json::Value::Null => out.push_str(&"null".magenta().to_string()),
+        serde_json::Value::Bool(v) => out.push_str(&v.to_string().yellow().to_string()),
+        serde_json::Value::Number(v) => out.push_str(&v.to_string().yellow().to_string()),
+        serde_json::Value::String(_) => out.push_str(&value.to_string().green().to_string()),
+        serde_json::Value::Array(values) if values.is_empty() => out.push_str("[]"),
+        serde_json::Value::Array(values) => {
+            out.push_str("[\n");
+            for (i, v) in values.iter().enumerate() {
+                out.push_str(&indent);
+                write_json(out, v, depth + 1);
+                out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push(']');
+        }
+        serde_json::Value::Object(values) if values.is_empty() => out.push_str("{}"),
+        serde_json::Value::Object(values) => {
+            out.push_str("{\n");
+            for (i, (k, v)) in values.iter().enumerate() {
+                let key = serde_json::Value::String(k.clone()).to_string();
+                _ = write!(out, "{}{}: ", indent, key.blue().bold());
+                write_json(out, v, depth + 1);
+                out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&INDENT.repeat(depth));
+            out.push('}');
+        }
+    }
+}
+
+/// xml or html with a tag per line, indented by nesting.
+pub fn markup(text: &str, html: bool) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            push_line(&mut out, depth, rest.trim(), false);
+            break;
+        };
+        push_line(&mut out, depth, rest[..start].trim(), false);
+        rest = &rest[start..];
+        let end = tag_end(rest);
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        let name = tag_name(tag);
+        if tag.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            push_line(&mut out, depth, tag, true);
+        } else if tag.starts_with("<!")
+            || tag.starts_with("<?")
+            || tag.ends_with("/>")
+            || (html && VOID_ELEMENTS.contains(&name.as_str()))
+        {
+            push_line(&mut out, depth, tag, true);
+        } else if html && RAW_ELEMENTS.contains(&name.as_str()) {
+            // content up to the closing tag is kept on the lines it came with
+            let close = format!("</{}", name);
+            let content_end = rest.to_ascii_lowercase().find(&close).unwrap_or(rest.len());
+            let content = &rest[..content_end];
+            rest = &rest[content_end..];
+            if content.contains('\n') {
+                push_line(&mut out, depth, tag, true);
+                out.push_str(content.trim_matches('\n'));
+                out.push('\n');
+                // the closing tag outdents again
+                depth += 1;
+            } else {
+                let close_end = tag_end(rest).min(rest.len());
+                let line = format!("{}{}{}", tag.blue(), content, rest[..close_end].blue());
+                rest = &rest[close_end..];
+                push_line(&mut out, depth, &line, false);
+            }
+        } else {
+            // a short text element stays on one line: <name>yurl</name>
+            let inline = rest.find('<').filter(|&i| {
+                rest[i..].starts_with(&format!("</{}", name)) && !rest[..i].contains('\n')
+            });
+            match inline {
+                Some(i) => {
+                    let close_end = i + tag_end(&rest[i..]);
+                    let line = format!(
+                        "{}{}{}",
+                        tag.blue(),
+                        rest[..i].trim(),
+                        rest[i..close_end].blue()
+                    );
+                    rest = &rest[close_end..];
+                    push_line(&mut out, depth, &line, false);
+                }
+                None => {
+                    push_line(&mut out, depth, tag, true);
+                    depth += 1;
+                }
+            }
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn push_line(out: &mut String, depth: usize, line: &str, tag: bool) {
+    if line.is_empty() {
+        return;
+    }
+    out.push_str(&INDENT.repeat(depth));
+    match tag {
+        true => out.push_str(&line.blue().to_string()),
+        false => out.push_str(line),
+    }
+    out.push('\n');
+}
+
+/// length of the tag at the start of text, quoted `>` and comments included.
+fn tag_end(text: &str) -> usize {
+    if text.starts_with("<!--") {
+        return text.find("-->").map_or(text.len(), |i| i + 3);
+    }
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches(['<', '/'])
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// `xxd` style dump: offset, 16 bytes in hex and their ascii.
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (i, line) in bytes.chunks(16).take(HEX_DUMP_LIMIT / 16).enumerate() {
+        _ = write!(out, "{}  ", format!("{:08x}", i * 16).dimmed());
+        for j in 0..16 {
+            match line.get(j) {
+                Some(b) => _ = write!(out, "{:02x} ", b),
+                None => out.push_str("   "),
+            }
+            if j == 7 {
+                out.push(' ');
+            }
+        }
+        let ascii: String = line
+            .iter()
+            .map(|&b| match b.is_ascii_graphic() || b == b' ' {
+                true => b as char,
+                false => '.',
+            })
+            .collect();
+        _ = writeln!(out, " |{}|", ascii);
+    }
+    if bytes.len() > HEX_DUMP_LIMIT {
+        _ = writeln!(
+            out,
+            "{}",
+            format!("... {} of {} bytes shown", HEX_DUMP_LIMIT, bytes.len()).dimmed()
+        );
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hex_dump, is_binary, json, markup};
+
+    #[test]
+    fn test_render() {
+        colored::control::set_override(false);
+        let value = serde_json::json!({"name": "yurl", "tags": [1, true], "empty": {}});
+        assert_eq!(json(&value), serde_json::to_string_pretty(&value).unwrap());
+        assert_eq!(
+            markup(
+                r#"<?xml version="1.0"?><a x=">"><b>yurl</b><c/></a>"#,
+                false
+            ),
+            "<?xml version=\"1.0\"?>\n<a x=\">\">\n  <b>yurl</b>\n  <c/>\n</a>"
+        );
+        assert_eq!(
+            markup("<html><body><br><p>hi</p></body></html>", true),
+            "<html>\n  <body>\n    <br>\n    <p>hi</p>\n  </body>\n</html>"
+        );
+        assert_eq!(
+            hex_dump(b"\x89PNG\r\n"),
+            "00000000  89 50 4e 47 0d 0a                                 |.PNG..|"
+        );
+        assert!(is_binary(Some("image/png"), b"\x89PNG\0"));
+        assert!(!is_binary(Some("application/json"), b"{}"));
+    }
+}
@@ -0,0 +1,500 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::core::error::YurlError;
+use crate::yurl_error;
+
+mod html;
+
+/// what a request sent and received, collected for `--output` and `--report`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Record {
+    /// template file of the request.
+    pub file: String,
+    pub name: String,
+    pub method: String,
+    /// url of the last hop, with the query and after redirects.
+    pub url: String,
+    pub request_headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub response_headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<serde_json::Value>,
+    /// raw response body when it is binary, shown as a hex dump.
+    #[serde(skip)]
+    pub response_bytes: Option<Vec<u8>>,
+    /// from the first byte sent to the response read, including redirects.
+    pub duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// not sent because an earlier request failed.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub skipped: bool,
+}
+
+impl Record {
+    pub fn new(file: &str, name: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// the request as sent, a later redirect hop replaces it.
+    pub fn request(&mut self, request: &ureq::Request, body: Option<&[u8]>) {
+        self.method = request.method().to_string();
+        self.url = request.url().to_string();
+        self.request_headers = request
+            .header_names()
+            .into_iter()
+            .map(|name| {
+                let values = request.all(&name).join(", ");
+                (name, values)
+            })
+            .collect();
+        self.request_body = body.map(|body| {
+            match self.request_headers.get("content-encoding") {
+                // compressed bytes are not readable
+                Some(encoding) => {
+                    serde_json::Value::String(format!("<{} body, {} bytes>", encoding, body.len()))
+                }
+                None => body_value(
+                    &String::from_utf8_lossy(body),
+                    self.request_headers.get("content-type"),
+                ),
+            }
+        });
+    }
+
+    pub fn response(&mut self, response: &ureq::Response) {
+        self.status = Some(response.status());
+        self.response_headers = response
+            .headers_names()
+            .into_iter()
+            .map(|name| {
+                let values = response.all(&name).join(", ");
+                (name.to_lowercase(), values)
+            })
+            .collect();
+    }
+
+    pub fn response_body(&mut self, body: &str) {
+        self.response_body = Some(body_value(body, self.response_headers.get("content-type")));
+    }
+
+    /// curl style trace of the request, `>` sent and `<` received lines.
+    /// bodies are printed when body_limit is set, cut after that many bytes, 0 keeps them whole.
+    pub fn trace(&self, body_limit: Option<usize>) -> String {
+        let mut trace = format!("* [{}] {}\n", self.name, self.file);
+        if self.skipped {
+            trace.push_str("* skipped, an earlier request failed\n");
+            return trace;
+        }
+        trace.push_str(&format!("> {} {}\n", self.method, self.url));
+        for (name, value) in &self.request_headers {
+            trace.push_str(&format!("> {}: {}\n", name, value));
+        }
+        trace.push_str(">\n");
+        if let (Some(limit), Some(body)) = (body_limit, &self.request_body) {
+            trace.push_str(&truncate(&body_text(body), limit));
+        }
+        if let Some(status) = self.status {
+            trace.push_str(&format!("< {} ({:.1} ms)\n", status, self.duration_ms));
+            for (name, value) in &self.response_headers {
+                trace.push_str(&format!("< {}: {}\n", name, value));
+            }
+            trace.push_str("<\n");
+            if let (Some(limit), Some(body)) = (body_limit, &self.response_body) {
+                trace.push_str(&truncate(&body_text(body), limit));
+            }
+        }
+        if let Some(error) = &self.error {
+            trace.push_str(&format!("* {} ({:.1} ms)\n", error, self.duration_ms));
+        }
+        trace
+    }
+}
+
+/// body as sent or received, json compact.
+fn body_text(body: &serde_json::Value) -> String {
+    match body {
+        serde_json::Value::String(text) => text.clone(),
+        json => json.to_string(),
+    }
+}
+
+/// cut the text after limit bytes on a char boundary, 0 keeps it whole.
+fn truncate(text: &str, limit: usize) -> String {
+    if limit == 0 || text.len() <= limit {
+        return format!("{}\n", text);
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n* body truncated, {} of {} bytes shown\n",
+        &text[..end],
+        end,
+        text.len()
+    )
+}
+
+/// json bodies are kept as json, others as text.
+fn body_value(body: &str, content_type: Option<&String>) -> serde_json::Value {
+    match content_type.is_some_and(|c| c.contains("json")) {
+        true => serde_json::from_str(body)
+            .unwrap_or_else(|_| serde_json::Value::String(body.to_string())),
+        false => serde_json::Value::String(body.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputFormat {
+    JSON,
+    NDJSON,
+}
+
+/// `--output json|ndjson[=path]`, written to stdout without a path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+    pub format: OutputFormat,
+    pub path: Option<String>,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match value.split_once('=') {
+            Some((format, path)) => (format, Some(path.to_string())),
+            None => (value, None),
+        };
+        let format = match format.to_lowercase().as_str() {
+            "json" => OutputFormat::JSON,
+            "ndjson" => OutputFormat::NDJSON,
+            _ => return Err(format!("expected json or ndjson, got {format}")),
+        };
+        Ok(Output { format, path })
+    }
+}
+
+impl Output {
+    pub fn open(&self) -> Result<OutputWriter, Box<dyn Error>> {
+        let writer: Box<dyn Write> = match &self.path {
+            Some(path) => Box::new(File::create(path).map_err(|e| -> Box<dyn Error> {
+                yurl_error!(&format!("create output {}: {}", path, e))
+            })?),
+            None => Box::new(io::stdout()),
+        };
+        Ok(OutputWriter {
+            format: self.format.clone(),
+            writer,
+            records: Vec::new(),
+        })
+    }
+}
+
+/// writes ndjson records as the requests finish, json records once at the end.
+pub struct OutputWriter {
+    format: OutputFormat,
+    writer: Box<dyn Write>,
+    records: Vec<Record>,
+}
+
+impl OutputWriter {
+    pub fn write(&mut self, record: &Record) -> Result<(), Box<dyn Error>> {
+        match self.format {
+            OutputFormat::JSON => self.records.push(record.clone()),
+            OutputFormat::NDJSON => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                writeln!(self.writer)?;
+                self.writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Box<dyn Error>> {
+        if self.format == OutputFormat::JSON {
+            serde_json::to_writer_pretty(&mut self.writer, &self.records)?;
+            writeln!(self.writer)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReportFormat {
+    JUNIT,
+    TAP,
+    HTML,
+}
+
+/// `--report junit|tap|html[=path]`, written to stdout without a path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub format: ReportFormat,
+    pub path: Option<String>,
+}
+
+impl FromStr for Report {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match value.split_once('=') {
+            Some((format, path)) => (format, Some(path.to_string())),
+            None => (value, None),
+        };
+        let format = match format.to_lowercase().as_str() {
+            "junit" => ReportFormat::JUNIT,
+            "tap" => ReportFormat::TAP,
+            "html" => ReportFormat::HTML,
+            _ => return Err(format!("expected junit, tap or html, got {format}")),
+        };
+        Ok(Report { format, path })
+    }
+}
+
+impl Report {
+    /// write the report of the records, one test case per request.
+    pub fn write(&self, records: &[Record]) -> Result<(), Box<dyn Error>> {
+        let report = match self.format {
+            ReportFormat::JUNIT => junit(records),
+            ReportFormat::TAP => tap(records),
+            ReportFormat::HTML => html::render(records),
+        };
+        match &self.path {
+            Some(path) => fs::write(path, report).map_err(|e| -> Box<dyn Error> {
+                yurl_error!(&format!("write report {}: {}", path, e))
+            })?,
+            None => io::stdout().write_all(report.as_bytes())?,
+        }
+        Ok(())
+    }
+}
+
+/// JUnit XML, a test suite per template file.
+fn junit(records: &[Record]) -> String {
+    let mut suites: Vec<(&str, Vec<&Record>)> = Vec::new();
+    for record in records {
+        match suites.iter_mut().find(|(file, _)| *file == record.file) {
+            Some((_, cases)) => cases.push(record),
+            None => suites.push((&record.file, vec![record])),
+        }
+    }
+    let count = |records: &[&Record]| {
+        let failures = records
+            .iter()
+            .filter(|r| r.error.is_some() && r.status.is_some());
+        let errors = records
+            .iter()
+            .filter(|r| r.error.is_some() && r.status.is_none());
+        let skipped = records.iter().filter(|r| r.skipped);
+        let time: f64 = records.iter().map(|r| r.duration_ms).sum::<f64>() / 1000.0;
+        format!(
+            r#"tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}""#,
+            records.len(),
+            failures.count(),
+            errors.count(),
+            skipped.count(),
+            time
+        )
+    };
+    let all: Vec<&Record> = records.iter().collect();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites name=\"yurl\" {}>\n", count(&all)));
+    for (file, cases) in suites {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" {}>\n",
+            xml_escape(file),
+            count(&cases)
+        ));
+        for record in cases {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(&record.name),
+                xml_escape(file),
+                record.duration_ms / 1000.0
+            ));
+            if record.skipped {
+                xml.push_str("      <skipped message=\"an earlier request failed\"/>\n");
+            }
+            if let Some(error) = &record.error {
+                // a response with a bad status is a failure, no response is an error
+                let (tag, kind) = match record.status {
+                    Some(_) => ("failure", "status"),
+                    None => ("error", "request"),
+                };
+                xml.push_str(&format!(
+                    "      <{tag} type=\"{kind}\" message=\"{}\">{}</{tag}>\n",
+                    xml_escape(error),
+                    xml_escape(&summary(record))
+                ));
+            }
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                xml_escape(&summary(record))
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// TAP version 13, failures carry a YAML diagnostic block.
+fn tap(records: &[Record]) -> String {
+    let mut tap = format!("TAP version 13\n1..{}\n", records.len());
+    let mut file = None;
+    for (i, record) in records.iter().enumerate() {
+        if file != Some(&record.file) {
+            tap.push_str(&format!("# {}\n", record.file));
+            file = Some(&record.file);
+        }
+        let name = record.name.replace('#', "\\#");
+        if record.skipped {
+            tap.push_str(&format!(
+                "ok {} - {} # SKIP an earlier request failed\n",
+                i + 1,
+                name
+            ));
+            continue;
+        }
+        match &record.error {
+            None => tap.push_str(&format!(
+                "ok {} - {} # time={:.3}ms\n",
+                i + 1,
+                name,
+                record.duration_ms
+            )),
+            Some(error) => {
+                tap.push_str(&format!("not ok {} - {}\n  ---\n", i + 1, name));
+                let mut diagnostic = serde_yaml::Mapping::new();
+                diagnostic.insert("message".into(), error.as_str().into());
+                diagnostic.insert("method".into(), record.method.as_str().into());
+                diagnostic.insert("url".into(), record.url.as_str().into());
+                if let Some(status) = record.status {
+                    diagnostic.insert("status".into(), u64::from(status).into());
+                }
+                diagnostic.insert("duration_ms".into(), record.duration_ms.into());
+                let yaml = serde_yaml::to_string(&diagnostic).unwrap_or_default();
+                for line in yaml.lines() {
+                    tap.push_str(&format!("  {line}\n"));
+                }
+                tap.push_str("  ...\n");
+            }
+        }
+    }
+    tap
+}
+
+/// `METHOD url -> status` line of a record.
+fn summary(record: &Record) -> String {
+    match record.status {
+        Some(status) => format!("{} {} -> {}", record.method, record.url, status),
+        None => format!("{} {}", record.method, record.url),
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // characters not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{junit, tap, truncate, Output, OutputFormat, Record};
+
+    #[test]
+    fn test_output() {
+        let output: Output = "ndjson=out.ndjson".parse().unwrap();
+        assert_eq!(output.format, OutputFormat::NDJSON);
+        assert_eq!(output.path.as_deref(), Some("out.ndjson"));
+        assert_eq!("JSON".parse::<Output>().unwrap().path, None);
+        assert!("xml".parse::<Output>().is_err());
+    }
+
+    #[test]
+    fn test_record() {
+        let request =
+            ureq::post("http://127.0.0.1:8080/a?b=1").set("Content-Type", "application/json");
+        let mut record = Record::new("template.yaml", "a");
+        record.request(&request, Some(br#"{"name":"yurl"}"#));
+        assert_eq!(record.method, "POST");
+        assert_eq!(record.url, "http://127.0.0.1:8080/a?b=1");
+        assert_eq!(record.request_headers["content-type"], "application/json");
+        assert_eq!(
+            record.request_body,
+            Some(serde_json::json!({"name": "yurl"}))
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mut ok = Record::new("api.yaml", "login");
+        ok.method = "POST".to_string();
+        ok.status = Some(200);
+        ok.duration_ms = 12.0;
+        let mut failed = Record::new("api.yaml", "user <1>");
+        failed.status = Some(404);
+        failed.error = Some("status code: 404".to_string());
+        let mut skipped = Record::new("api.yaml", "logout");
+        skipped.skipped = true;
+        let records = vec![ok, failed, skipped];
+        let xml = junit(&records);
+        assert!(xml.contains(r#"<testsuite name="api.yaml" tests="3" failures="1" errors="0" skipped="1" time="0.012">"#));
+        assert!(xml.contains(r#"<testcase name="user &lt;1&gt;" classname="api.yaml""#));
+        assert!(xml.contains(r#"<failure type="status" message="status code: 404">"#));
+        let tap = tap(&records);
+        assert!(tap.starts_with("TAP version 13\n1..3\n# api.yaml\nok 1 - login"));
+        assert!(tap.contains("not ok 2 - user <1>\n  ---\n  message: 'status code: 404'"));
+        assert!(tap.contains("ok 3 - logout # SKIP"));
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut record = Record::new("api.yaml", "login");
+        record.method = "POST".to_string();
+        record.url = "http://localhost/login".to_string();
+        record
+            .request_headers
+            .insert("content-type".to_string(), "application/json".to_string());
+        record.request_body = Some(serde_json::json!({"user": "yurl"}));
+        record.status = Some(200);
+        record.response_body = Some(serde_json::json!("ok"));
+        let trace = record.trace(None);
+        assert!(trace
+            .contains("> POST http://localhost/login\n> content-type: application/json\n>\n< 200"));
+        assert!(!trace.contains("yurl"));
+        let trace = record.trace(Some(0));
+        assert!(trace.contains(">\n{\"user\":\"yurl\"}\n< 200"));
+        assert_eq!(
+            truncate("héllo", 2),
+            "h\n* body truncated, 1 of 6 bytes shown\n"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use super::{xml_escape, Record};
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #24292f; }
+h1 { font-size: 1.4em; margin-bottom: 0.2em; }
+.meta { color: #57606a; margin-bottom: 1.5em; }
+.summary span { display: inline-block; margin-right: 1.5em; font-size: 1.1em; }
+details { border: 1px solid #d0d7de; border-radius: 6px; margin: 0.5em 0; }
+summary { cursor: pointer; padding: 0.6em 0.8em; font-family: monospace; }
+details[open] summary { border-bottom: 1px solid #d0d7de; }
+.body { padding: 0.5em 1em; }
+.badge { display: inline-block; min-width: 3.5em; text-align: center; border-radius: 4px; color: #fff; font-weight: bold; margin-right: 0.6em; }
+.pass { background: #1a7f37; }
+.fail { background: #cf222e; }
+.skip { background: #8c959f; }
+.time { float: right; color: #57606a; }
+.error { color: #cf222e; white-space: pre-wrap; }
+table { border-collapse: collapse; font-family: monospace; font-size: 0.9em; }
+td { border: 1px solid #d0d7de; padding: 0.2em 0.6em; vertical-align: top; }
+pre { background: #f6f8fa; padding: 0.8em; overflow: auto; max-height: 30em; }
+"#;
+
+/// single static html page, a collapsible entry per request.
+pub fn render(records: &[Record]) -> String {
+    let passed = records
+        .iter()
+        .filter(|r| r.error.is_none() && !r.skipped)
+        .count();
+    let failed = records.iter().filter(|r| r.error.is_some()).count();
+    let skipped = records.iter().filter(|r| r.skipped).count();
+    let time: f64 = records.iter().map(|r| r.duration_ms).sum();
+    let mut files: Vec<&str> = Vec::new();
+    for record in records {
+        if !files.contains(&record.file.as_str()) {
+            files.push(&record.file);
+        }
+    }
+    let mut html =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>yurl report</title>\n");
+    html.push_str(&format!("<style>{STYLE}</style>\n</head>\n<body>\n"));
+    html.push_str("<h1>yurl report</h1>\n");
+    html.push_str(&format!(
+        "<div class=\"meta\">{} &middot; {}</div>\n",
+        xml_escape(&files.join(", ")),
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+    html.push_str(&format!(
+        "<div class=\"summary\"><span>{} requests</span><span class=\"badge pass\">{}</span>passed \
+         <span class=\"badge fail\">{}</span>failed <span class=\"badge skip\">{}</span>skipped \
+         <span>{:.1} ms</span></div>\n",
+        records.len(),
+        passed,
+        failed,
+        skipped,
+        time
+    ));
+    for record in records {
+        html.push_str(&entry(record));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn entry(record: &Record) -> String {
+    let (class, label) = match (record.skipped, &record.error) {
+        (true, _) => ("skip", "SKIP"),
+        (false, Some(_)) => ("fail", "FAIL"),
+        (false, None) => ("pass", "PASS"),
+    };
+    let status = record.status.map(|s| s.to_string()).unwrap_or_default();
+    // failures are opened so they are seen first
+    let open = if record.error.is_some() { " open" } else { "" };
+    let mut html = format!(
+        "<details{open}>\n<summary><span class=\"badge {class}\">{label}</span>{} {} {} <b>{}</b>\
+         <span class=\"time\">{:.1} ms</span></summary>\n<div class=\"body\">\n",
+        xml_escape(&record.method),
+        xml_escape(&record.url),
+        status,
+        xml_escape(&record.name),
+        record.duration_ms
+    );
+    if let Some(error) = &record.error {
+        html.push_str(&format!("<p class=\"error\">{}</p>\n", xml_escape(error)));
+    }
+    if !record.skipped {
+        html.push_str(&section(
+            "Request",
+            &record.request_headers,
+            &record.request_body,
+        ));
+        html.push_str(&section(
+            "Response",
+            &record.response_headers,
+            &record.response_body,
+        ));
+    }
+    html.push_str("</div>\n</details>\n");
+    html
+}
+
+fn section(
+    title: &str,
+    headers: &BTreeMap<String, String>,
+    body: &Option<serde_json::Value>,
+) -> String {
+    let mut html = format!("<h3>{title}</h3>\n");
+    if !headers.is_empty() {
+        html.push_str("<table>\n");
+        for (name, value) in headers {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                xml_escape(name),
+                xml_escape(value)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    if let Some(body) = body {
+        let body = match body {
+            serde_json::Value::String(text) => text.clone(),
+            json => serde_json::to_string_pretty(json).unwrap_or_default(),
+        };
+        html.push_str(&format!("<pre>{}</pre>\n", xml_escape(&body)));
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+    use crate::core::report::Record;
+
+    #[test]
+    fn test_render() {
+        let mut ok = Record::new("api.yaml", "login");
+        ok.method = "POST".to_string();
+        ok.status = Some(200);
+        ok.response_body = Some(serde_json::json!({"token": "<t>"}));
+        let mut failed = Record::new("api.yaml", "user");
+        failed.status = Some(500);
+        failed.error = Some("status code: 500".to_string());
+        let html = render(&[ok, failed]);
+        assert!(html.contains("<span>2 requests</span><span class=\"badge pass\">1</span>passed"));
+        assert!(html.contains("<pre>{\n  &quot;token&quot;: &quot;&lt;t&gt;&quot;\n}</pre>"));
+        assert!(html.contains("<details open>\n<summary><span class=\"badge fail\">FAIL</span>"));
+    }
+}
@@ -1,214 +1,492 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
 use std::{cmp::Ordering, path::Path};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Mapping;
 
 use crate::core::error::YurlError;
 use crate::yurl_error;
 
-use super::multipart::MultipartBuilder;
+use super::auth::Auth;
+use super::client::{Client, Config};
+use super::compress::{
+    body_bytes, body_reader, body_string, Compress, ACCEPT_ENCODING, CONTENT_ENCODING_KEY,
+};
+use super::multipart::{MultipartBody, MultipartBuilder, Part};
+use super::render;
+use super::report::Record;
+use super::sign::Sign;
+use super::unix;
 
 const CONTENT_TYPE_KEY: &str = "Content-Type";
+const COOKIE_KEY: &str = "Cookie";
+pub const DEFAULT_REDIRECTS: u32 = 5;
 const CONTENT_TYPE_JSON: &str = "application/json";
 const CONTENT_TYPE_FROM: &str = "application/x-www-form-urlencoded";
 const CONTENT_TYPE_URL: &str = "application/x-www-form-urlencoded";
 const CONTENT_TYPE_FILE: &str = "multipart/form-data";
+/// headers not sent to another origin on redirect, besides names containing
+/// one of SENSITIVE_HEADER_WORDS, like ureq, curl and browsers do.
+const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+const SENSITIVE_HEADER_WORDS: [&str; 5] = ["key", "token", "secret", "session", "auth"];
 
-#[derive(Debug, Serialize, Deserialize)]
+/// a request of the template, requests are sent by `order`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Request {
     pub order: i32,
     pub name: String,
     pub url: String,
     pub method: Method,
+    #[schemars(schema_with = "crate::core::schema::scalar_map")]
     pub headers: HashMap<String, String>,
+    #[schemars(with = "serde_json::Value")]
     pub params: serde_yaml::Value,
     pub content_type: ContentType,
     pub response_type: ResponseType,
+    #[serde(default)]
+    pub array_format: ArrayFormat,
+    /// labels selected by `yurl run --tag` and `--exclude-tag`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// file path or directory for response_type FILE, can use expression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_to: Option<String>,
+    /// connection settings of this request, override the template defaults.
+    #[serde(flatten)]
+    pub config: Config,
+    /// cookies sent with this request besides the cookie jar, can use expression.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[schemars(schema_with = "crate::core::schema::scalar_map")]
+    pub cookies: HashMap<String, String>,
+    /// authentication of this request, overrides the template defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// compress JSON and FORM bodies, sent with Content-Encoding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compress: Option<Compress>,
+    /// request signing, applied after auth over the final body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign: Option<Sign>,
     #[serde(skip)]
     pub response: Option<String>,
+    /// template file the request was read from.
+    #[serde(skip)]
+    pub file: String,
 }
 
 impl Request {
-    pub fn run(&self) -> Result<String, Box<dyn Error>> {
-        match self.method {
-            Method::GET => self.get(),
-            Method::POST => self.post(),
-            Method::PUT => self.put(),
-            Method::DELETE => self.delete(),
+    /// send the request, what is sent and received is kept in the record.
+    pub fn run(&self, client: &mut Client, record: &mut Record) -> Result<String, Box<dyn Error>> {
+        let start = Instant::now();
+        let result = self.send(client, record);
+        record.duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        if let Err(e) = &result {
+            record.error = Some(e.to_string());
         }
+        result
     }
 
-    fn get(&self) -> Result<String, Box<dyn Error>> {
-        let request = ureq::request("GET", &self.url);
-        self.execute(request)
-    }
-
-    fn post(&self) -> Result<String, Box<dyn Error>> {
-        let request = ureq::request("POST", &self.url);
-        self.execute(request)
-    }
-
-    fn put(&self) -> Result<String, Box<dyn Error>> {
-        let request = ureq::request("PUT", &self.url);
-        self.execute(request)
-    }
-
-    fn delete(&self) -> Result<String, Box<dyn Error>> {
-        let request = ureq::request("DELETE", &self.url);
-        self.execute(request)
+    fn send(&self, client: &mut Client, record: &mut Record) -> Result<String, Box<dyn Error>> {
+        let mut config = client.config(&self.config);
+        // `unix:///app.sock:/path` or a unix_socket with a url path
+        let mut url = match unix::split_url(&self.url)? {
+            (Some(socket), url) => {
+                config.unix_socket = Some(socket);
+                url
+            }
+            (None, url) if config.unix_socket.is_some() && url.starts_with('/') => {
+                unix::local_url(&url)
+            }
+            (None, url) => url,
+        };
+        let redirects = config.redirects.unwrap_or(DEFAULT_REDIRECTS);
+        let origin = url::Url::parse(&url)?.origin();
+        let no_cookies = HashMap::new();
+        let mut method = self.method.as_str();
+        let mut with_body = true;
+        let mut hop = 0;
+        let auth = client.auth(&self.auth);
+        let sign = client.sign(&self.sign);
+        let mut challenge: Option<String> = None;
+        let mut retried = false;
+        let response = loop {
+            let same_origin = url::Url::parse(&url)?.origin() == origin;
+            // redirects may leave or enter the NO_PROXY hosts
+            let mut request = client.agent(&config, &url)?.request(method, &url);
+            if with_body {
+                let content_type = self.content_type.to_kv();
+                request = request.set(content_type.0, content_type.1);
+            }
+            if let Some(authorization) = config.proxy_authorization(&url::Url::parse(&url)?) {
+                request = request.set("Proxy-Authorization", &authorization);
+            }
+            request = request.set("Accept-Encoding", ACCEPT_ENCODING);
+            for (k, v) in self.headers.iter() {
+                if same_origin || !is_sensitive_header(k) {
+                    request = request.set(k, v);
+                }
+            }
+            // another origin only gets the cookies the jar holds for it
+            let cookies = match same_origin {
+                true => &self.cookies,
+                false => &no_cookies,
+            };
+            if let Some(cookie) = client.cookie_header(request.url(), cookies)? {
+                let cookie = match request.header(COOKIE_KEY) {
+                    Some(header) => format!("{header}; {cookie}"),
+                    None => cookie,
+                };
+                request = request.set(COOKIE_KEY, &cookie);
+            }
+            // credentials only go to the origin of the request
+            if let Some(auth) = auth.as_ref().filter(|_| same_origin) {
+                request = client.authorize(auth, request, hop == 0, challenge.as_deref())?;
+            }
+            // an absolute redirect to another origin leaves the socket
+            let unix = Some(&config).filter(|c| same_origin && c.unix_socket.is_some());
+            let response = match with_body {
+                true => self.execute(request, hop == 0, sign.as_ref(), unix, record),
+                false => Body::Empty.send(request, sign.as_ref(), unix, record),
+            };
+            // error statuses are still responses, their cookies must be kept
+            let response = match response.map_err(|e| e.downcast::<ureq::Error>()) {
+                Ok(response) => response,
+                Err(Ok(e)) => match *e {
+                    ureq::Error::Status(_, response) => response,
+                    e => return Err(Box::new(e)),
+                },
+                Err(Err(e)) => return Err(e),
+            };
+            client.store_cookies(&url, &response)?;
+            // answer the challenge or renew the token once
+            if let Some(auth) = auth
+                .as_ref()
+                .filter(|a| same_origin && a.retry_unauthorized())
+            {
+                if response.status() == 401 && !retried {
+                    retried = true;
+                    challenge = response
+                        .all("WWW-Authenticate")
+                        .into_iter()
+                        .find(|h| h.trim_start().to_lowercase().starts_with("digest"))
+                        .map(|h| h.to_string());
+                    client.invalidate(auth);
+                    continue;
+                }
+            }
+            let location = response.header("Location").map(|l| l.to_string());
+            match location {
+                Some(location) if (300..400).contains(&response.status()) && hop < redirects => {
+                    url = url::Url::parse(&url)?.join(&location)?.to_string();
+                    // like browsers, only 307 and 308 repeat the method and body
+                    if !matches!(response.status(), 307 | 308) {
+                        with_body = false;
+                        if method != "HEAD" {
+                            method = "GET";
+                        }
+                    }
+                    hop += 1;
+                }
+                // a redirect loop must not pass as the 3xx response
+                Some(_) if (300..400).contains(&response.status()) && redirects > 0 => {
+                    record.response(&response);
+                    return Err(yurl_error!(&format!(
+                        "request name: [{}], url: [{}] execute fail, maximum redirects ({}) followed",
+                        self.name, self.url, redirects
+                    )));
+                }
+                _ => break response,
+            }
+        };
+        record.response(&response);
+        self.check(&url, response, redirects, record)
     }
 
-    fn execute(&self, mut request: ureq::Request) -> Result<String, Box<dyn Error>> {
-        let content_type = self.content_type.to_kv();
-        request = request.set(content_type.0, content_type.1);
-        for (k, v) in self.headers.iter() {
-            request = request.set(k, v);
-        }
-        let response: ureq::Response;
-        match self.content_type {
+    /// encode the body, sign the request over it and send it.
+    fn execute(
+        &self,
+        mut request: ureq::Request,
+        query: bool,
+        sign: Option<&Sign>,
+        unix: Option<&Config>,
+        record: &mut Record,
+    ) -> Result<ureq::Response, Box<dyn Error>> {
+        let body = match self.content_type {
             ContentType::URLENCODED => {
-                for (k, v) in self.params.as_mapping().unwrap_or(&Mapping::new()) {
-                    match v {
-                        serde_yaml::Value::Null => {
-                            request = request.query(k.as_str().unwrap(), "");
-                        }
-                        serde_yaml::Value::Bool(v) => {
-                            request = request.query(k.as_str().unwrap(), &format!("{v}"));
-                        }
-                        serde_yaml::Value::Number(v) => {
-                            request = request.query(k.as_str().unwrap(), &format!("{v}"));
-                        }
-                        serde_yaml::Value::String(v) => {
-                            request = request.query(k.as_str().unwrap(), v);
-                        }
-                        serde_yaml::Value::Sequence(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_URL
-                            )))
-                        }
-                        serde_yaml::Value::Mapping(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_URL
-                            )))
-                        }
-                        serde_yaml::Value::Tagged(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_URL
-                            )))
-                        }
+                // redirected urls already carry the query
+                if query {
+                    for (k, v) in self.array_format.flatten(&self.params, CONTENT_TYPE_URL)? {
+                        request = request.query(&k, &v);
                     }
                 }
-                response = request.call()?
+                Body::Empty
             }
             ContentType::FORM => {
-                let mut body: Vec<(&str, String)> = Vec::new();
-                for (k, v) in self.params.as_mapping().unwrap() {
-                    match v {
-                        serde_yaml::Value::Null => {
-                            body.push((k.as_str().unwrap(), "".to_string()));
-                        }
-                        serde_yaml::Value::Bool(v) => {
-                            body.push((k.as_str().unwrap(), v.to_string()));
-                        }
-                        serde_yaml::Value::Number(v) => {
-                            body.push((k.as_str().unwrap(), v.to_string()));
-                        }
-                        serde_yaml::Value::String(v) => {
-                            body.push((k.as_str().unwrap(), v.to_string()));
-                        }
-                        serde_yaml::Value::Sequence(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_FROM
-                            )))
-                        }
-                        serde_yaml::Value::Mapping(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_FROM
-                            )))
-                        }
-                        serde_yaml::Value::Tagged(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_FROM
-                            )))
-                        }
-                    }
-                }
-                let body: Vec<(&str, &str)> = body.iter().map(|m| (m.0, m.1.as_str())).collect();
-                response = request.send_form(&body[..])?
+                let body = self.array_format.flatten(&self.params, CONTENT_TYPE_FROM)?;
+                let body = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(body)
+                    .finish();
+                Body::Bytes(body.into_bytes())
             }
-            ContentType::JSON => response = request.send_json(&self.params)?,
+            ContentType::JSON => Body::Bytes(serde_json::to_vec(&self.params)?),
             ContentType::FILE => {
-                if self.method != Method::POST {
-                    return Err(yurl_error!("file request only support POST"));
-                }
                 let mut multipart = MultipartBuilder::new();
-                for (k, v) in self.params.as_mapping().unwrap() {
-                    match v {
-                        serde_yaml::Value::Null => {
-                            multipart = multipart.add_text(k.as_str().unwrap(), "")?;
-                        }
-                        serde_yaml::Value::Bool(v) => {
-                            multipart = multipart.add_text(k.as_str().unwrap(), &format!("{v}"))?;
-                        }
-                        serde_yaml::Value::Number(v) => {
-                            multipart = multipart.add_text(k.as_str().unwrap(), &format!("{v}"))?;
-                        }
-                        serde_yaml::Value::String(v) => {
-                            if v.starts_with("FILE(") && v.ends_with(")") {
-                                multipart = multipart
-                                    .add_file(k.as_str().unwrap(), Path::new(&v[5..v.len() - 1]))?;
-                            } else {
-                                multipart = multipart.add_text(k.as_str().unwrap(), v)?;
-                            }
-                        }
-                        serde_yaml::Value::Sequence(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_FILE
-                            )))
-                        }
-                        serde_yaml::Value::Mapping(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_FILE
-                            )))
-                        }
-                        serde_yaml::Value::Tagged(_) => {
-                            return Err(yurl_error!(&format!(
-                                "{} complex object not supported as parameters",
-                                CONTENT_TYPE_FILE
-                            )))
-                        }
+                let parts = self.array_format.flatten_values(
+                    &self.params,
+                    CONTENT_TYPE_FILE,
+                    Part::is_part,
+                )?;
+                for (k, v) in parts {
+                    if Part::is_part(&v) {
+                        let part: Part = serde_yaml::from_value(v.into_owned())?;
+                        multipart = multipart.add_part(&k, &part)?;
+                        continue;
+                    }
+                    let v = scalar_to_string(&v).unwrap_or_default();
+                    if v.starts_with("FILE(") && v.ends_with(')') {
+                        multipart = multipart.add_file(&k, Path::new(&v[5..v.len() - 1]))?;
+                    } else {
+                        multipart = multipart.add_text(&k, &v)?;
                     }
                 }
-                let (content_type, data) = multipart.finish()?;
-                response = request
-                    .set(CONTENT_TYPE_KEY, &content_type)
-                    .send_bytes(&data)?
+                let (content_type, body) = multipart.finish_stream()?;
+                Body::Multipart(content_type, body)
             }
-        }
-        if response.status() == 200 {
-            Ok(response.into_string()?)
+        };
+        let body = match (body, &self.compress) {
+            (Body::Bytes(bytes), Some(compress)) => {
+                request = request.set(CONTENT_ENCODING_KEY, compress.content_encoding());
+                Body::Bytes(compress.compress(&bytes)?)
+            }
+            (body, _) => body,
+        };
+        body.send(request, sign, unix, record)
+    }
+
+    fn check(
+        &self,
+        url: &str,
+        response: ureq::Response,
+        redirects: u32,
+        record: &mut Record,
+    ) -> Result<String, Box<dyn Error>> {
+        // a 3xx is the expected response only when redirects are disabled
+        let status = response.status();
+        if (200..300).contains(&status) || ((300..400).contains(&status) && redirects == 0) {
+            let body = self.read_response(url, response, record)?;
+            record.response_body(&body);
+            Ok(body)
         } else {
+            let status = response.status();
+            let status_text = response.status_text().to_string();
+            if let Ok(body) = body_string(response) {
+                record.response_body(&body);
+            }
             return Err(yurl_error!(&format!(
                 "request name: [{}], url: [{}] execute fail, status code: {}, message: {}",
-                self.name,
-                self.url,
-                response.status(),
-                response.status_text()
+                self.name, self.url, status, status_text
             )));
         }
     }
 }
 
+impl Request {
+    fn read_response(
+        &self,
+        url: &str,
+        response: ureq::Response,
+        record: &mut Record,
+    ) -> Result<String, Box<dyn Error>> {
+        match self.response_type {
+            ResponseType::TEXT | ResponseType::HTML => {
+                let content_type = response.header(CONTENT_TYPE_KEY).map(|c| c.to_string());
+                let (bytes, body) = body_bytes(response)?;
+                if render::is_binary(content_type.as_deref(), &bytes) {
+                    record.response_bytes = Some(bytes);
+                }
+                Ok(body)
+            }
+            ResponseType::JSON => {
+                let body = body_string(response)?;
+                if let Err(e) = serde_json::from_str::<serde_json::Value>(&body) {
+                    return Err(yurl_error!(&format!(
+                        "request name: [{}] response is not valid json: {}",
+                        self.name, e
+                    )));
+                }
+                Ok(body)
+            }
+            ResponseType::FILE => {
+                let path = self.save_path(url, &response)?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut file = File::create(&path)?;
+                io::copy(&mut body_reader(response), &mut file)?;
+                Ok(path.display().to_string())
+            }
+        }
+    }
+
+    /// file path of a FILE response, save_to may be a file or a directory,
+    /// the file name falls back to Content-Disposition and then the url path.
+    fn save_path(&self, url: &str, response: &ureq::Response) -> Result<PathBuf, Box<dyn Error>> {
+        if let Some(save_to) = &self.save_to {
+            let path = PathBuf::from(save_to);
+            if !save_to.ends_with('/') && !path.is_dir() {
+                return Ok(path);
+            }
+        }
+        let filename = response
+            .header("Content-Disposition")
+            .and_then(disposition_filename)
+            .or_else(|| {
+                let url = url.split(['?', '#']).next().unwrap_or_default();
+                let url = url.split_once("://").map_or(url, |(_, u)| u);
+                url.split_once('/')
+                    .and_then(|(_, path)| path.rsplit('/').next())
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.to_string())
+            })
+            .ok_or_else(|| -> Box<dyn Error> {
+                yurl_error!(&format!(
+                    "request name: [{}] response file name is unknown, please set save_to",
+                    self.name
+                ))
+            })?;
+        // never let the server choose a path outside of the target directory
+        let filename = Path::new(&filename)
+            .file_name()
+            .map(|f| f.to_os_string())
+            .ok_or_else(|| -> Box<dyn Error> {
+                yurl_error!(&format!("invalid response file name: {}", filename))
+            })?;
+        Ok(match &self.save_to {
+            Some(dir) => PathBuf::from(dir).join(filename),
+            None => PathBuf::from(filename),
+        })
+    }
+}
+
+/// encoded request body, multipart files are streamed unless the request is signed.
+enum Body {
+    Empty,
+    Bytes(Vec<u8>),
+    Multipart(String, MultipartBody),
+}
+
+impl Body {
+    fn send(
+        self,
+        mut request: ureq::Request,
+        sign: Option<&Sign>,
+        unix: Option<&Config>,
+        record: &mut Record,
+    ) -> Result<ureq::Response, Box<dyn Error>> {
+        let body = match (self, sign) {
+            // the signature covers the bytes sent, so a multipart body is read first
+            (Body::Multipart(content_type, mut body), Some(_)) => {
+                request = request.set(CONTENT_TYPE_KEY, &content_type);
+                let mut bytes = Vec::new();
+                io::Read::read_to_end(&mut body, &mut bytes)?;
+                Body::Bytes(bytes)
+            }
+            (Body::Multipart(content_type, body), None) => {
+                request = request.set(CONTENT_TYPE_KEY, &content_type);
+                Body::Multipart(content_type, body)
+            }
+            (body, _) => body,
+        };
+        if let Some(sign) = sign {
+            let bytes = match &body {
+                Body::Bytes(bytes) => bytes.as_slice(),
+                _ => b"",
+            };
+            request = sign.apply(request, bytes)?;
+        }
+        match &body {
+            Body::Bytes(bytes) => record.request(&request, Some(bytes)),
+            Body::Multipart(..) => {
+                // streamed from the files, the boundary is in the Content-Type header
+                record.request(&request, None);
+                record.request_body = Some(serde_json::Value::String("<multipart body>".into()));
+            }
+            Body::Empty => record.request(&request, None),
+        }
+        if let Some(config) = unix {
+            return match body {
+                Body::Empty => unix::send(config, &request, None),
+                Body::Bytes(bytes) => {
+                    let length = bytes.len() as u64;
+                    unix::send(
+                        config,
+                        &request,
+                        Some((&mut bytes.as_slice(), Some(length))),
+                    )
+                }
+                Body::Multipart(_, mut body) => {
+                    let length = body.content_length();
+                    unix::send(config, &request, Some((&mut body, length)))
+                }
+            };
+        }
+        Ok(match body {
+            Body::Empty => request.call()?,
+            Body::Bytes(bytes) => request.send_bytes(&bytes)?,
+            Body::Multipart(content_type, body) => body.send(request, &content_type)?,
+        })
+    }
+}
+
+/// file name from a Content-Disposition header, `filename*` is preferred.
+fn disposition_filename(disposition: &str) -> Option<String> {
+    let mut filename = None;
+    for param in disposition.split(';').map(|p| p.trim()) {
+        if let Some(v) = param.strip_prefix("filename*=") {
+            // RFC 5987: charset'language'percent-encoded
+            let v = v.splitn(3, '\'').nth(2).unwrap_or(v);
+            return Some(percent_decode(v.trim_matches('"')));
+        }
+        if let Some(v) = param.strip_prefix("filename=") {
+            filename = Some(v.trim_matches('"').to_string());
+        }
+    }
+    filename.filter(|f| !f.is_empty())
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_HEADERS.contains(&name.as_str())
+        || SENSITIVE_HEADER_WORDS.iter().any(|w| name.contains(w))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            // the bytes after % may start a multibyte char, the str is never sliced
+            let hex = |b: u8| (b as char).to_digit(16);
+            if let (Some(high), Some(low)) = (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                decoded.push((high * 16 + low) as u8);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
 impl Eq for Request {}
 
 impl PartialEq for Request {
@@ -249,20 +527,42 @@ impl Default for Request {
             params: serde_yaml::Value::Mapping(p),
             content_type: ContentType::URLENCODED,
             response_type: ResponseType::JSON,
+            array_format: ArrayFormat::default(),
+            tags: Vec::new(),
+            save_to: None,
+            config: Config::default(),
+            cookies: HashMap::new(),
+            auth: None,
+            compress: None,
+            sign: None,
             response: Default::default(),
+            file: Default::default(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum Method {
     GET,
     POST,
     PUT,
     DELETE,
+    PATCH,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::GET => "GET",
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::DELETE => "DELETE",
+            Method::PATCH => "PATCH",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum ContentType {
     URLENCODED,
     FORM,
@@ -281,7 +581,133 @@ impl ContentType {
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// flattened param name and value
+type Param<'a> = (String, Cow<'a, serde_yaml::Value>);
+
+/// how sequences and mappings in params are encoded as key/value pairs
+/// for URLENCODED, FORM and FILE requests, mappings always use `user[name]=x`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum ArrayFormat {
+    /// `a=1&a=2`
+    #[default]
+    REPEAT,
+    /// `a[]=1&a[]=2`
+    BRACKETS,
+    /// `a[0]=1&a[1]=2`
+    INDICES,
+    /// `a=1,2`
+    COMMA,
+}
+
+impl ArrayFormat {
+    /// flatten params into ordered key/value pairs
+    ///
+    /// * params request params, must be a mapping or null
+    /// * content_type used in the error message
+    pub fn flatten(
+        &self,
+        params: &serde_yaml::Value,
+        content_type: &str,
+    ) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        Ok(self
+            .flatten_values(params, content_type, |_| false)?
+            .into_iter()
+            .map(|(k, v)| (k, scalar_to_string(&v).unwrap_or_default()))
+            .collect())
+    }
+
+    /// flatten params into ordered key/value pairs, keeping the values
+    ///
+    /// * params request params, must be a mapping or null
+    /// * content_type used in the error message
+    /// * is_leaf complex values that must not be flattened any further
+    pub fn flatten_values<'a>(
+        &self,
+        params: &'a serde_yaml::Value,
+        content_type: &str,
+        is_leaf: fn(&serde_yaml::Value) -> bool,
+    ) -> Result<Vec<Param<'a>>, Box<dyn Error>> {
+        let mut pairs = Vec::new();
+        match params {
+            serde_yaml::Value::Null => {}
+            serde_yaml::Value::Mapping(m) => {
+                for (k, v) in m {
+                    let key = scalar_to_string(k).ok_or_else(|| -> Box<dyn Error> {
+                        yurl_error!(&format!("{} parameter name must be a scalar", content_type))
+                    })?;
+                    self.flatten_value(key, v, content_type, is_leaf, &mut pairs)?;
+                }
+            }
+            _ => {
+                return Err(yurl_error!(&format!(
+                    "{} parameters must be a mapping",
+                    content_type
+                )))
+            }
+        }
+        Ok(pairs)
+    }
+
+    fn flatten_value<'a>(
+        &self,
+        key: String,
+        value: &'a serde_yaml::Value,
+        content_type: &str,
+        is_leaf: fn(&serde_yaml::Value) -> bool,
+        pairs: &mut Vec<Param<'a>>,
+    ) -> Result<(), Box<dyn Error>> {
+        if is_leaf(value) {
+            pairs.push((key, Cow::Borrowed(value)));
+            return Ok(());
+        }
+        match value {
+            serde_yaml::Value::Sequence(seq) => {
+                if *self == ArrayFormat::COMMA && seq.iter().all(|v| scalar_to_string(v).is_some())
+                {
+                    let joined: Vec<String> = seq.iter().filter_map(scalar_to_string).collect();
+                    pairs.push((key, Cow::Owned(serde_yaml::Value::String(joined.join(",")))));
+                    return Ok(());
+                }
+                for (i, v) in seq.iter().enumerate() {
+                    let child = match (self, is_leaf(v) || scalar_to_string(v).is_some()) {
+                        (ArrayFormat::REPEAT, true) => key.clone(),
+                        (ArrayFormat::BRACKETS, true) => format!("{key}[]"),
+                        _ => format!("{key}[{i}]"),
+                    };
+                    self.flatten_value(child, v, content_type, is_leaf, pairs)?;
+                }
+            }
+            serde_yaml::Value::Mapping(m) => {
+                for (k, v) in m {
+                    let k = scalar_to_string(k).ok_or_else(|| -> Box<dyn Error> {
+                        yurl_error!(&format!("{} parameter name must be a scalar", content_type))
+                    })?;
+                    self.flatten_value(format!("{key}[{k}]"), v, content_type, is_leaf, pairs)?;
+                }
+            }
+            serde_yaml::Value::Tagged(_) => {
+                return Err(yurl_error!(&format!(
+                    "{} tagged value not supported as parameters",
+                    content_type
+                )))
+            }
+            v => pairs.push((key, Cow::Borrowed(v))),
+        }
+        Ok(())
+    }
+}
+
+pub fn scalar_to_string(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Null => Some(String::new()),
+        serde_yaml::Value::Bool(v) => Some(v.to_string()),
+        serde_yaml::Value::Number(v) => Some(v.to_string()),
+        serde_yaml::Value::String(v) => Some(v.to_string()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub enum ResponseType {
     TEXT,
     JSON,
@@ -293,7 +719,24 @@ pub enum ResponseType {
 mod tests {
     use serde_yaml::Value;
 
-    use super::Request;
+    use crate::core::auth::Auth;
+    use crate::core::client::{Client, Defaults};
+    use crate::core::report::Record;
+
+    use super::{disposition_filename, ArrayFormat, Request};
+
+    /// answer one request on the listener, the request head is returned.
+    fn serve(listener: std::net::TcpListener, response: String) -> std::thread::JoinHandle<String> {
+        std::thread::spawn(move || {
+            use std::io::{BufRead, BufReader, Write};
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            while reader.read_line(&mut head).unwrap() > 2 && !head.ends_with("\r\n\r\n") {}
+            stream.write_all(response.as_bytes()).unwrap();
+            head
+        })
+    }
 
     #[test]
     fn test_run() {
@@ -308,13 +751,199 @@ content_type: FORM
 response_type: JSON
 "#;
         let request: Request = serde_yaml::from_str(request_yaml).unwrap();
-        let resp = request.run().unwrap();
+        let resp = request
+            .run(
+                &mut Client::new(Defaults::default()),
+                &mut Record::default(),
+            )
+            .unwrap();
         assert_eq!(
             resp,
             "{\"code\":200,\"message\":\"success\",\"data\":{\"name\":\"post-form\"}}"
         );
     }
 
+    #[test]
+    fn test_redirect_origin() {
+        let first = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let second = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let location = format!("http://{}/next", second.local_addr().unwrap());
+        let first_url = format!("http://{}/start", first.local_addr().unwrap());
+        let first = serve(
+            first,
+            format!("HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+        );
+        let second = serve(
+            second,
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+        );
+        let request_yaml = format!(
+            r#"order: 1
+name: redirect
+url: {first_url}
+method: GET
+headers:
+  Accept: text/plain
+  Authorization: Basic dG9tOnB3
+  X-Api-Key: key-1
+params:
+content_type: URLENCODED
+response_type: TEXT
+cookies:
+  sid: abc
+"#
+        );
+        let request: Request = serde_yaml::from_str(&request_yaml).unwrap();
+        let mut client = Client::new(Defaults::default());
+        let mut record = Record::new("", "redirect");
+        assert_eq!(request.run(&mut client, &mut record).unwrap(), "ok");
+        let first = first.join().unwrap().to_lowercase();
+        let second = second.join().unwrap().to_lowercase();
+        for header in [
+            "authorization: basic",
+            "x-api-key: key-1",
+            "cookie: sid=abc",
+        ] {
+            assert!(first.contains(header), "{header} not sent: {first}");
+            assert!(!second.contains(header), "{header} sent to another origin");
+        }
+        assert!(second.contains("accept: text/plain"));
+    }
+
+    #[test]
+    fn test_redirect_limit() {
+        let redirect = "HTTP/1.1 302 Found\r\nLocation: /loop\r\nContent-Length: 5\r\nConnection: close\r\n\r\nmoved";
+        let request = |url: &str, redirects: u32| -> Request {
+            serde_yaml::from_str(&format!(
+                r#"order: 1
+name: loop
+url: {url}
+method: GET
+headers:
+params:
+content_type: URLENCODED
+response_type: TEXT
+redirects: {redirects}
+"#
+            ))
+            .unwrap()
+        };
+        // the first response and 2 redirects followed
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/loop", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            for _ in 0..3 {
+                let listener = listener.try_clone().unwrap();
+                serve(listener, redirect.to_string()).join().unwrap();
+            }
+        });
+        let mut client = Client::new(Defaults::default());
+        let error = request(&url, 2)
+            .run(&mut client, &mut Record::new("", "loop"))
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("maximum redirects (2) followed"),
+            "{error}"
+        );
+        server.join().unwrap();
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/loop", listener.local_addr().unwrap());
+        let server = serve(listener, redirect.to_string());
+        let body = request(&url, 0)
+            .run(&mut client, &mut Record::new("", "loop"))
+            .unwrap();
+        assert_eq!(body, "moved");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_redirect_auth() {
+        let first = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let second = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let location = format!("http://{}/next", second.local_addr().unwrap());
+        let first_url = format!("http://{}/start", first.local_addr().unwrap());
+        let first = serve(
+            first,
+            format!("HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+        );
+        let second = serve(
+            second,
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+        );
+        let request = Request {
+            url: first_url,
+            headers: Default::default(),
+            params: Value::Null,
+            content_type: super::ContentType::URLENCODED,
+            response_type: super::ResponseType::TEXT,
+            auth: Some(Auth::BEARER {
+                token: "tok-1".to_string(),
+            }),
+            ..Default::default()
+        };
+        let mut client = Client::new(Defaults::default());
+        let mut record = Record::new("", "redirect");
+        assert_eq!(request.run(&mut client, &mut record).unwrap(), "ok");
+        assert!(first.join().unwrap().contains("Bearer tok-1"));
+        assert!(!second.join().unwrap().contains("tok-1"));
+    }
+
+    #[test]
+    fn test_array_format() {
+        let params: Value = serde_yaml::from_str(
+            r#"ids: [1, 2]
+user:
+  name: tom
+  roles: [admin]
+"#,
+        )
+        .unwrap();
+        let encode = |format: ArrayFormat| -> Vec<String> {
+            format
+                .flatten(&params, "test")
+                .unwrap()
+                .into_iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect()
+        };
+        assert_eq!(
+            encode(ArrayFormat::REPEAT),
+            ["ids=1", "ids=2", "user[name]=tom", "user[roles]=admin"]
+        );
+        assert_eq!(
+            encode(ArrayFormat::BRACKETS),
+            ["ids[]=1", "ids[]=2", "user[name]=tom", "user[roles][]=admin"]
+        );
+        assert_eq!(
+            encode(ArrayFormat::INDICES),
+            ["ids[0]=1", "ids[1]=2", "user[name]=tom", "user[roles][0]=admin"]
+        );
+        assert_eq!(
+            encode(ArrayFormat::COMMA),
+            ["ids=1,2", "user[name]=tom", "user[roles]=admin"]
+        );
+    }
+
+    #[test]
+    fn test_disposition_filename() {
+        assert_eq!(
+            disposition_filename("attachment; filename=\"report.pdf\"").unwrap(),
+            "report.pdf"
+        );
+        assert_eq!(
+            disposition_filename("attachment; filename=a.txt; filename*=UTF-8''%E6%8A%A5.txt")
+                .unwrap(),
+            "报.txt"
+        );
+        assert_eq!(disposition_filename("inline"), None);
+        // a multibyte char right after % is kept as is
+        assert_eq!(
+            disposition_filename("attachment; filename*=UTF-8''%aé").unwrap(),
+            "%aé"
+        );
+    }
+
     #[test]
     fn test_yaml_parse() {
         let yaml = r#"vars:
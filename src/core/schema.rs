@@ -0,0 +1,63 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::schema_for;
+
+use crate::core::Template;
+
+/// schema file written next to generated templates.
+pub const SCHEMA_FILE: &str = "yurl.schema.json";
+
+/// JSON Schema of the template yaml, derived from the serde structs.
+pub fn schema() -> String {
+    let schema = schema_for!(Template);
+    serde_json::to_string_pretty(&schema).unwrap_or_default()
+}
+
+/// string map whose plain yaml numbers and booleans are read as strings, like headers.
+pub fn scalar_map(_: &mut SchemaGenerator) -> Schema {
+    let scalar = SchemaObject {
+        instance_type: Some(
+            vec![
+                InstanceType::String,
+                InstanceType::Number,
+                InstanceType::Boolean,
+            ]
+            .into(),
+        ),
+        ..Default::default()
+    };
+    let mut map = SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        ..Default::default()
+    };
+    map.object().additional_properties = Some(Box::new(scalar.into()));
+    map.into()
+}
+
+/// first line of a template, lets yaml-language-server editors complete and validate it.
+pub fn yaml_language_server_hint(schema: &str) -> String {
+    format!("# yaml-language-server: $schema={}\n", schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::schema;
+
+    #[test]
+    fn test_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&schema()).unwrap();
+        let methods = &schema["definitions"]["Method"]["enum"];
+        assert_eq!(
+            methods,
+            &serde_json::json!(["GET", "POST", "PUT", "DELETE", "PATCH"])
+        );
+        let request = &schema["definitions"]["Request"];
+        assert!(request["properties"]["timeout"].is_object());
+        assert!(request["properties"]["response"].is_null());
+        // the shipped schema is regenerated with `yurl schema -o yurl.schema.json`
+        assert_eq!(
+            include_str!("../../yurl.schema.json").trim_end(),
+            super::schema()
+        );
+    }
+}
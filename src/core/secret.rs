@@ -0,0 +1,277 @@
+use std::error::Error;
+
+use crate::core::auth::Auth;
+use crate::core::error::YurlError;
+use crate::core::report::Record;
+use crate::core::request::Request;
+use crate::core::sign::Sign;
+use crate::yurl_error;
+
+pub const MASK: &str = "***";
+/// headers whose values are always masked.
+const SECRET_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-amz-security-token",
+];
+
+/// values replaced by `***` in every output: the template `secrets:` variables,
+/// `res.<request>.<path>` values once the request ran, auth and sign credentials.
+#[derive(Debug, Default)]
+pub struct Secrets {
+    /// `res.` names not resolved yet
+    responses: Vec<String>,
+    values: Vec<String>,
+}
+
+impl Secrets {
+    /// resolve the variable names of template `secrets:`, `var.` is optional.
+    pub fn new(names: &[String], variables: &[serde_yaml::Value]) -> Result<Self, Box<dyn Error>> {
+        let mut secrets = Secrets::default();
+        for name in names {
+            if name.starts_with("res.") {
+                secrets.responses.push(name.clone());
+                continue;
+            }
+            let key = name.strip_prefix("var.").unwrap_or(name);
+            let value = variables
+                .iter()
+                .find_map(|variable| key.split('.').try_fold(variable, |v, k| v.get(k)))
+                .ok_or_else(|| -> Box<dyn Error> {
+                    yurl_error!(&format!("undefined secret variable: {}", name))
+                })?;
+            match value {
+                serde_yaml::Value::String(v) => secrets.add(v),
+                serde_yaml::Value::Number(v) => secrets.add(&v.to_string()),
+                _ => return Err(yurl_error!(&format!("secret {} is not a string", name))),
+            }
+        }
+        Ok(secrets)
+    }
+
+    /// the value and its url encoded form, as sent in a query or a FORM body.
+    pub fn add(&mut self, value: &str) {
+        let encoded: String = url::form_urlencoded::byte_serialize(value.as_bytes()).collect();
+        for value in [value.to_string(), encoded] {
+            if !value.is_empty() && !self.values.contains(&value) {
+                self.values.push(value);
+            }
+        }
+        // longest first, so a secret containing another is masked whole
+        self.values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+    }
+
+    /// the credentials of the auth.
+    pub fn add_auth(&mut self, auth: &Auth) {
+        match auth {
+            Auth::BASIC { password, .. } | Auth::DIGEST { password, .. } => self.add(password),
+            Auth::BEARER { token } => self.add(token),
+            Auth::APIKEY { value, .. } => self.add(value),
+            Auth::OAUTH2 {
+                client_secret,
+                password,
+                ..
+            } => {
+                for value in [client_secret, password].into_iter().flatten() {
+                    self.add(value);
+                }
+            }
+        }
+    }
+
+    /// the keys of the sign.
+    pub fn add_sign(&mut self, sign: &Sign) {
+        match sign {
+            Sign::AWS4 {
+                secret_key,
+                session_token,
+                ..
+            } => {
+                for value in std::iter::once(secret_key).chain(session_token) {
+                    self.add(value);
+                }
+            }
+            Sign::HMAC { secret, .. } => self.add(secret),
+        }
+    }
+
+    /// values of the `res.<name>.<path>` secrets of the request response.
+    pub fn add_response(&mut self, name: &str, response: &str) {
+        let prefix = format!("res.{}.", name);
+        let Ok(response) = serde_json::from_str::<serde_json::Value>(response) else {
+            return;
+        };
+        let paths: Vec<String> = self
+            .responses
+            .iter()
+            .filter_map(|secret| secret.strip_prefix(&prefix))
+            .map(|path| path.to_string())
+            .collect();
+        for path in paths {
+            let value = path
+                .split('.')
+                .try_fold(&response, |v, k| match k.strip_prefix('#') {
+                    Some(i) => v.get(i.parse::<usize>().ok()?),
+                    None => v.get(k),
+                });
+            match value {
+                Some(serde_json::Value::String(v)) => self.add(v),
+                Some(serde_json::Value::Number(v)) => self.add(&v.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn mask(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for value in &self.values {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), MASK);
+            }
+        }
+        text
+    }
+
+    /// the value, or `***` for a secret header.
+    pub fn mask_header(&self, name: &str, value: &str) -> String {
+        match SECRET_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name)) {
+            true => MASK.to_string(),
+            false => self.mask(value),
+        }
+    }
+
+    /// headers and cookies of the request before it is printed, cookie values are always `***`.
+    pub fn mask_request(&self, request: &mut Request) {
+        for (name, value) in request.headers.iter_mut() {
+            *value = self.mask_header(name, value);
+        }
+        for value in request.cookies.values_mut() {
+            *value = MASK.to_string();
+        }
+    }
+
+    pub fn mask_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::String(v) => *v = self.mask(v),
+            serde_json::Value::Array(values) => values.iter_mut().for_each(|v| self.mask_json(v)),
+            serde_json::Value::Object(values) => {
+                values.values_mut().for_each(|v| self.mask_json(v))
+            }
+            _ => {}
+        }
+    }
+
+    pub fn mask_record(&self, record: &mut Record) {
+        record.url = self.mask(&record.url);
+        for (name, value) in record
+            .request_headers
+            .iter_mut()
+            .chain(record.response_headers.iter_mut())
+        {
+            *value = self.mask_header(name, value);
+        }
+        for body in [&mut record.request_body, &mut record.response_body]
+            .into_iter()
+            .flatten()
+        {
+            self.mask_json(body);
+        }
+        if let Some(error) = &record.error {
+            record.error = Some(self.mask(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Secrets;
+    use crate::core::auth::Auth;
+    use crate::core::report::Record;
+    use crate::core::request::Request;
+    use crate::core::sign::Sign;
+
+    #[test]
+    fn test_mask_record() {
+        let variables = vec![serde_yaml::from_str("api: {key: k-123}").unwrap()];
+        let mut secrets = Secrets::new(
+            &["api.key".to_string(), "res.login.token".to_string()],
+            &variables,
+        )
+        .unwrap();
+        assert!(Secrets::new(&["missing".to_string()], &variables).is_err());
+        secrets.add_auth(&Auth::BEARER {
+            token: "t-456".to_string(),
+        });
+        secrets.add_response("login", r#"{"token":"t-789"}"#);
+        let mut record = Record::new("api.yaml", "user");
+        record.url = "http://localhost/user?key=k-123".to_string();
+        record
+            .request_headers
+            .insert("authorization".to_string(), "Basic eXVybA==".to_string());
+        record
+            .request_headers
+            .insert("x-token".to_string(), "t-456".to_string());
+        record.response_body = Some(serde_json::json!({"items": ["t-789"]}));
+        record.error = Some("bad key k-123".to_string());
+        secrets.mask_record(&mut record);
+        assert_eq!(record.url, "http://localhost/user?key=***");
+        assert_eq!(record.request_headers["authorization"], "***");
+        assert_eq!(record.request_headers["x-token"], "***");
+        assert_eq!(
+            record.response_body,
+            Some(serde_json::json!({"items": ["***"]}))
+        );
+        assert_eq!(record.error.as_deref(), Some("bad key ***"));
+    }
+
+    #[test]
+    fn test_mask_sign() {
+        let mut secrets = Secrets::default();
+        secrets.add_sign(&Sign::AWS4 {
+            access_key: "AKID".to_string(),
+            secret_key: "s/k+1 2".to_string(),
+            session_token: Some("tok=3".to_string()),
+            region: "us-east-1".to_string(),
+            service: "s3".to_string(),
+        });
+        secrets.add_sign(&serde_yaml::from_str("{type: HMAC, secret: h&4}").unwrap());
+        let mut record = Record::new("api.yaml", "user");
+        record.url = "http://localhost/?a=s%2Fk%2B1+2&b=h%264".to_string();
+        record
+            .request_headers
+            .insert("x-amz-security-token".to_string(), "anything".to_string());
+        record.request_body = Some(serde_json::json!("k=tok%3D3&s=s/k+1 2"));
+        secrets.mask_record(&mut record);
+        assert_eq!(record.url, "http://localhost/?a=***&b=***");
+        assert_eq!(record.request_headers["x-amz-security-token"], "***");
+        assert_eq!(record.request_body, Some(serde_json::json!("k=***&s=***")));
+        assert_eq!(secrets.mask("AKID"), "AKID");
+    }
+
+    #[test]
+    fn test_mask_request() {
+        let mut secrets = Secrets::default();
+        secrets.add("k-123");
+        let mut request = Request {
+            headers: [
+                ("Authorization", "Bearer literal-secret"),
+                ("Cookie", "sid=cookie-secret"),
+                ("X-Key", "k-123"),
+                ("Accept", "text/plain"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+            cookies: [("sid2".to_string(), "jar-secret".to_string())].into(),
+            ..Default::default()
+        };
+        secrets.mask_request(&mut request);
+        assert_eq!(request.headers["Authorization"], "***");
+        assert_eq!(request.headers["Cookie"], "***");
+        assert_eq!(request.headers["X-Key"], "***");
+        assert_eq!(request.headers["Accept"], "text/plain");
+        assert_eq!(request.cookies["sid2"], "***");
+    }
+}
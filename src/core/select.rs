@@ -0,0 +1,137 @@
+use std::error::Error;
+
+use regex::Regex;
+
+use crate::core::error::YurlError;
+use crate::core::expression::Expression;
+use crate::core::request::Request;
+use crate::yurl_error;
+
+/// which requests of a template run, every request when nothing is set.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// request names
+    pub only: Vec<String>,
+    /// requests with any of these tags
+    pub tags: Vec<String>,
+    /// requests without any of these tags
+    pub exclude_tags: Vec<String>,
+    /// requests whose name matches
+    pub grep: Option<Regex>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty()
+            && self.tags.is_empty()
+            && self.exclude_tags.is_empty()
+            && self.grep.is_none()
+    }
+
+    fn matches(&self, request: &Request) -> bool {
+        (self.only.is_empty() || self.only.contains(&request.name))
+            && (self.tags.is_empty() || request.tags.iter().any(|t| self.tags.contains(t)))
+            && !request.tags.iter().any(|t| self.exclude_tags.contains(t))
+            && self.grep.as_ref().is_none_or(|r| r.is_match(&request.name))
+    }
+
+    /// the matching requests and the requests their `${res...}` expressions
+    /// depend on, transitively, in template order.
+    pub fn select(&self, requests: Vec<Request>) -> Result<Vec<Request>, Box<dyn Error>> {
+        if self.is_empty() {
+            return Ok(requests);
+        }
+        for name in &self.only {
+            if !requests.iter().any(|r| &r.name == name) {
+                return Err(yurl_error!(&format!("request [{}] does not exist", name)));
+            }
+        }
+        let mut selected: Vec<bool> = requests.iter().map(|r| self.matches(r)).collect();
+        if !selected.contains(&true) {
+            return Err(yurl_error!("no request matches the filters"));
+        }
+        let mut pending: Vec<usize> = (0..requests.len()).filter(|&i| selected[i]).collect();
+        while let Some(i) = pending.pop() {
+            for name in dependencies(&requests[i])? {
+                // a response restored from a session has no request here
+                if let Some(j) = requests.iter().position(|r| r.name == name) {
+                    if !selected[j] {
+                        selected[j] = true;
+                        pending.push(j);
+                    }
+                }
+            }
+        }
+        Ok(requests
+            .into_iter()
+            .zip(selected)
+            .filter_map(|(request, selected)| selected.then_some(request))
+            .collect())
+    }
+}
+
+/// names of the requests whose responses the request uses.
+pub fn dependencies(request: &Request) -> Result<Vec<String>, Box<dyn Error>> {
+    let yaml = serde_yaml::to_string(request)?;
+    let mut names = Vec::new();
+    for expression in Expression::parse_from_str(&yaml)? {
+        if let Ok(Expression::Response(expr)) = Expression::parse(&expression) {
+            let name = Expression::response_parse(&expr)?.parent;
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::Filter;
+    use crate::core::request::Request;
+
+    fn request(name: &str, url: &str, tags: &[&str]) -> Request {
+        Request {
+            name: name.to_string(),
+            url: url.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let requests = || {
+            vec![
+                request("login", "http://localhost/login", &["auth"]),
+                request("user", "http://localhost/${res.login.id}", &["slow"]),
+                request("orders", "http://localhost/${res.user.id}", &["smoke"]),
+                request("health", "http://localhost/health", &["smoke", "slow"]),
+            ]
+        };
+        let names = |requests: Vec<Request>| -> Vec<String> {
+            requests.into_iter().map(|r| r.name).collect()
+        };
+        let filter = Filter {
+            tags: vec!["smoke".to_string()],
+            exclude_tags: vec!["slow".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            names(filter.select(requests()).unwrap()),
+            ["login", "user", "orders"]
+        );
+        let filter = Filter {
+            grep: Some(Regex::new("^he").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(names(filter.select(requests()).unwrap()), ["health"]);
+        let filter = Filter {
+            only: vec!["missing".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.select(requests()).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use cookie_store::Cookie;
+use serde::{Deserialize, Serialize};
+
+/// state kept between separate runs: the cookie jar and the request responses
+/// that `${res...}` expressions read.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(default)]
+    pub cookies: Vec<Cookie<'static>>,
+    #[serde(default)]
+    pub responses: HashMap<String, String>,
+}
+
+impl Session {
+    /// load the session file, a missing file is an empty session.
+    pub fn load(file: &str) -> Result<Self, Box<dyn Error>> {
+        if !Path::new(file).exists() {
+            return Ok(Session::default());
+        }
+        let json = fs::read_to_string(file)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn save(&self, file: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(file, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie_store::Cookie;
+
+    use super::Session;
+
+    #[test]
+    fn test_save_load() {
+        let file = std::env::temp_dir().join("yurl_test_session.json");
+        let file = file.to_str().unwrap();
+        let url = url::Url::parse("http://127.0.0.1:8000/login").unwrap();
+        let mut session = Session::default();
+        session
+            .cookies
+            .push(Cookie::parse("token=abc; Path=/", &url).unwrap());
+        session
+            .responses
+            .insert("login".to_string(), "{\"token\":\"abc\"}".to_string());
+        session.save(file).unwrap();
+        let loaded = Session::load(file).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(loaded.cookies.len(), 1);
+        assert_eq!(loaded.cookies[0].value(), "abc");
+        assert_eq!(loaded.responses["login"], "{\"token\":\"abc\"}");
+    }
+}
@@ -0,0 +1,410 @@
+use std::error::Error;
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use url::Url;
+
+use crate::core::error::YurlError;
+use crate::yurl_error;
+
+/// request signing, set on a request or in template `defaults`.
+/// the signature covers the encoded body, every value can use expression.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type")]
+pub enum Sign {
+    /// AWS Signature Version 4, signs host, content-type and x-amz-* headers.
+    AWS4 {
+        access_key: String,
+        secret_key: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_token: Option<String>,
+        region: String,
+        service: String,
+    },
+    /// generic HMAC over the canonical request:
+    /// `METHOD\nPATH\nSORTED_QUERY\nname:value\n...\nHEX(SHA256(BODY))`
+    HMAC {
+        secret: String,
+        #[serde(default)]
+        algorithm: HmacAlgorithm,
+        #[serde(default)]
+        encoding: SignatureEncoding,
+        /// request headers included in the canonical request, in this order.
+        #[serde(default)]
+        headers: Vec<String>,
+        /// header set to the unix timestamp and signed, example: X-Timestamp
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timestamp_header: Option<String>,
+        /// header that receives the signature.
+        #[serde(default = "default_signature_header")]
+        header: String,
+        /// header value, `{signature}` `{timestamp}` and `{headers}` are replaced.
+        #[serde(default = "default_signature_format")]
+        format: String,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum HmacAlgorithm {
+    #[default]
+    SHA256,
+    SHA512,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum SignatureEncoding {
+    #[default]
+    HEX,
+    BASE64,
+}
+
+fn default_signature_header() -> String {
+    "Authorization".to_string()
+}
+
+fn default_signature_format() -> String {
+    "{signature}".to_string()
+}
+
+impl Sign {
+    /// sign the request over the exact body bytes that will be sent.
+    pub fn apply(
+        &self,
+        request: ureq::Request,
+        body: &[u8],
+    ) -> Result<ureq::Request, Box<dyn Error>> {
+        self.apply_at(request, body, Utc::now())
+    }
+
+    fn apply_at(
+        &self,
+        mut request: ureq::Request,
+        body: &[u8],
+        now: DateTime<Utc>,
+    ) -> Result<ureq::Request, Box<dyn Error>> {
+        let url = Url::parse(request.url())?;
+        match self {
+            Sign::AWS4 {
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                service,
+            } => {
+                let payload_hash = hex_sha256(body);
+                request = request
+                    .set("Host", &host(&url)?)
+                    .set("X-Amz-Date", &now.format("%Y%m%dT%H%M%SZ").to_string());
+                if service == "s3" {
+                    request = request.set("X-Amz-Content-Sha256", &payload_hash);
+                }
+                if let Some(token) = session_token {
+                    request = request.set("X-Amz-Security-Token", token);
+                }
+                let mut headers: Vec<(String, String)> = request
+                    .header_names()
+                    .into_iter()
+                    .filter(|h| h == "host" || h == "content-type" || h.starts_with("x-amz-"))
+                    .map(|h| {
+                        let value = request.header(&h).unwrap_or_default();
+                        (h, normalize_space(value))
+                    })
+                    .collect();
+                headers.sort();
+                headers.dedup_by(|a, b| a.0 == b.0);
+                let authorization = aws4_authorization(
+                    request.method(),
+                    &url,
+                    &headers,
+                    &payload_hash,
+                    access_key,
+                    secret_key,
+                    region,
+                    service,
+                    now,
+                )?;
+                request = request.set("Authorization", &authorization);
+            }
+            Sign::HMAC {
+                secret,
+                algorithm,
+                encoding,
+                headers,
+                timestamp_header,
+                header,
+                format,
+            } => {
+                let timestamp = now.timestamp().to_string();
+                let mut headers = headers.clone();
+                if let Some(timestamp_header) = timestamp_header {
+                    request = request.set(timestamp_header, &timestamp);
+                    if !headers
+                        .iter()
+                        .any(|h| h.eq_ignore_ascii_case(timestamp_header))
+                    {
+                        headers.push(timestamp_header.to_string());
+                    }
+                }
+                let mut canonical = format!(
+                    "{}\n{}\n{}\n",
+                    request.method(),
+                    url.path(),
+                    canonical_query(&url, false)
+                );
+                for name in &headers {
+                    let value = request.header(name).unwrap_or_default();
+                    canonical.push_str(&format!(
+                        "{}:{}\n",
+                        name.to_lowercase(),
+                        normalize_space(value)
+                    ));
+                }
+                canonical.push_str(&hex_sha256(body));
+                let signature = match algorithm {
+                    HmacAlgorithm::SHA256 => hmac_sha256(secret.as_bytes(), &canonical)?,
+                    HmacAlgorithm::SHA512 => {
+                        let mut mac = Hmac::<Sha512>::new_from_slice(secret.as_bytes())?;
+                        mac.update(canonical.as_bytes());
+                        mac.finalize().into_bytes().to_vec()
+                    }
+                };
+                let signature = match encoding {
+                    SignatureEncoding::HEX => hex(&signature),
+                    SignatureEncoding::BASE64 => {
+                        base64::engine::general_purpose::STANDARD.encode(signature)
+                    }
+                };
+                let names: Vec<String> = headers.iter().map(|h| h.to_lowercase()).collect();
+                let value = format
+                    .replace("{signature}", &signature)
+                    .replace("{timestamp}", &timestamp)
+                    .replace("{headers}", &names.join(";"));
+                request = request.set(header, &value);
+            }
+        }
+        Ok(request)
+    }
+}
+
+/// Authorization header of AWS Signature Version 4
+///
+/// * headers lowercase names, sorted, with normalized values
+#[allow(clippy::too_many_arguments)]
+fn aws4_authorization(
+    method: &str,
+    url: &Url,
+    headers: &[(String, String)],
+    payload_hash: &str,
+    access_key: &str,
+    secret_key: &str,
+    region: &str,
+    service: &str,
+    now: DateTime<Utc>,
+) -> Result<String, Box<dyn Error>> {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let path = canonical_uri(url, service);
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{k}:{v}\n")).collect();
+    let signed_headers: Vec<&str> = headers.iter().map(|(k, _)| k.as_str()).collect();
+    let signed_headers = signed_headers.join(";");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        path,
+        canonical_query(url, true),
+        canonical_headers,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex_sha256(canonical_request.as_bytes())
+    );
+    let mut key = hmac_sha256(format!("AWS4{secret_key}").as_bytes(), &date)?;
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part)?;
+    }
+    let signature = hex(&hmac_sha256(&key, &string_to_sign)?);
+    Ok(format!(
+        "AWS4-HMAC-SHA256 Credential={access_key}/{scope}, SignedHeaders={signed_headers}, Signature={signature}"
+    ))
+}
+
+/// query pairs sorted by name then value, `aws` uses the strict AWS uri encoding.
+fn canonical_query(url: &Url, aws: bool) -> String {
+    let encode = |s: &str| -> String {
+        if aws {
+            aws_uri_encode(s)
+        } else {
+            url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+        }
+    };
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (encode(&k), encode(&v)))
+        .collect();
+    pairs.sort();
+    let pairs: Vec<String> = pairs.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    pairs.join("&")
+}
+
+/// path as sent, already encoded once, each segment is encoded again for every service but s3.
+fn canonical_uri(url: &Url, service: &str) -> String {
+    match (url.path(), service) {
+        ("", _) => "/".to_string(),
+        (path, "s3") => path.to_string(),
+        (path, _) => path
+            .split('/')
+            .map(aws_uri_encode)
+            .collect::<Vec<String>>()
+            .join("/"),
+    }
+}
+
+fn aws_uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// host header value, the port is kept when it is not the scheme default.
+fn host(url: &Url) -> Result<String, Box<dyn Error>> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| -> Box<dyn Error> { yurl_error!(&format!("url without host: {}", url)) })?;
+    Ok(match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+fn normalize_space(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)?;
+    mac.update(data.as_bytes());
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use url::Url;
+
+    use super::{aws4_authorization, canonical_uri, hex_sha256, Sign};
+
+    #[test]
+    fn test_aws4_authorization() {
+        // example from the AWS Signature Version 4 documentation
+        let url =
+            Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08").unwrap();
+        let headers = vec![
+            (
+                "content-type".to_string(),
+                "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+            ),
+            ("host".to_string(), "iam.amazonaws.com".to_string()),
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        let authorization = aws4_authorization(
+            "GET",
+            &url,
+            &headers,
+            &hex_sha256(b""),
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "us-east-1",
+            "iam",
+            Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            authorization,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, SignedHeaders=content-type;host;x-amz-date, Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn test_canonical_uri() {
+        let url = Url::parse("https://example.amazonaws.com/a b/c:d@e=f,(g)*/%FF").unwrap();
+        assert_eq!(
+            canonical_uri(&url, "execute-api"),
+            "/a%2520b/c%3Ad%40e%3Df%2C%28g%29%2A/%25FF"
+        );
+        assert_eq!(canonical_uri(&url, "s3"), "/a%20b/c:d@e=f,(g)*/%FF");
+        let url = Url::parse("https://example.amazonaws.com").unwrap();
+        assert_eq!(canonical_uri(&url, "iam"), "/");
+        // signature checked against botocore
+        let url = Url::parse("https://example.amazonaws.com/stage/a b").unwrap();
+        let headers = vec![
+            ("host".to_string(), "example.amazonaws.com".to_string()),
+            ("x-amz-date".to_string(), "20150830T123600Z".to_string()),
+        ];
+        let authorization = aws4_authorization(
+            "GET",
+            &url,
+            &headers,
+            &hex_sha256(b""),
+            "AKIDEXAMPLE",
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "us-east-1",
+            "execute-api",
+            Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+        )
+        .unwrap();
+        assert!(authorization.ends_with(
+            "Signature=7cc9a90c7297ecc8ec2dd4cf4a67c26dfe8528b3d61158dcd2d68dbccb419416"
+        ));
+    }
+
+    #[test]
+    fn test_hmac() {
+        let sign: Sign = serde_yaml::from_str(
+            r#"type: HMAC
+secret: key
+headers: [Content-Type]
+timestamp_header: X-Timestamp
+header: X-Signature
+format: "v1 {headers} {signature}"
+"#,
+        )
+        .unwrap();
+        let request =
+            ureq::post("http://127.0.0.1:8000/a?b=2&a=1").set("Content-Type", "text/plain");
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let request = sign.apply_at(request, b"hello", now).unwrap();
+        assert_eq!(request.header("X-Timestamp"), Some("1704067200"));
+        let canonical = format!(
+            "POST\n/a\na=1&b=2\ncontent-type:text/plain\nx-timestamp:1704067200\n{}",
+            hex_sha256(b"hello")
+        );
+        let expected = super::hex(&super::hmac_sha256(b"key", &canonical).unwrap());
+        assert_eq!(
+            request.header("X-Signature").unwrap(),
+            format!("v1 content-type;x-timestamp {expected}")
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::core::error::YurlError;
+use crate::yurl_error;
+
+/// tls settings, used by template `defaults`, requests and cli flags.
+/// file paths are PEM files relative to the working directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub struct Tls {
+    /// extra CA certificates trusted besides the built-in roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+    /// client certificate chain for mutual tls, requires key_file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_file: Option<String>,
+    /// skip the server certificate verification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub insecure: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<TlsVersion>,
+    /// name sent as SNI and verified against the certificate instead of the url host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum TlsVersion {
+    TLS1_2,
+    TLS1_3,
+}
+
+impl Tls {
+    /// merge two tls settings, the values of other take precedence.
+    pub fn merge(&self, other: &Tls) -> Tls {
+        Tls {
+            ca_file: other.ca_file.clone().or_else(|| self.ca_file.clone()),
+            cert_file: other.cert_file.clone().or_else(|| self.cert_file.clone()),
+            key_file: other.key_file.clone().or_else(|| self.key_file.clone()),
+            insecure: other.insecure.or(self.insecure),
+            min_version: other
+                .min_version
+                .clone()
+                .or_else(|| self.min_version.clone()),
+            server_name: other
+                .server_name
+                .clone()
+                .or_else(|| self.server_name.clone()),
+        }
+    }
+
+    /// ureq tls connector of these settings.
+    pub fn connector(&self) -> Result<Arc<Connector>, Box<dyn Error>> {
+        Ok(Arc::new(Connector {
+            config: Arc::new(self.client_config()?),
+            server_name: self.server_name.clone(),
+        }))
+    }
+
+    fn client_config(&self) -> Result<ClientConfig, Box<dyn Error>> {
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_version {
+            Some(TlsVersion::TLS1_3) => &[&rustls::version::TLS13],
+            _ => rustls::ALL_VERSIONS,
+        };
+        let builder = ClientConfig::builder_with_protocol_versions(versions);
+        let builder = match self.insecure {
+            Some(true) => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoVerifier)),
+            _ => {
+                let mut roots = RootCertStore {
+                    roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+                };
+                if let Some(ca_file) = &self.ca_file {
+                    for cert in read_certs(ca_file)? {
+                        roots.add(cert)?;
+                    }
+                }
+                builder.with_root_certificates(roots)
+            }
+        };
+        let config = match (&self.cert_file, &self.key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                builder.with_client_auth_cert(read_certs(cert_file)?, read_key(key_file)?)?
+            }
+            (None, None) => builder.with_no_client_auth(),
+            _ => {
+                return Err(yurl_error!(
+                    "tls cert_file and key_file must be set together"
+                ))
+            }
+        };
+        Ok(config)
+    }
+}
+
+fn read_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| -> Box<dyn Error> { yurl_error!(&format!("read {}: {}", path, e)) })?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file)).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(yurl_error!(&format!("no PEM certificate in {}", path)));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &str) -> Result<PrivateKeyDer<'static>, Box<dyn Error>> {
+    let file = File::open(path)
+        .map_err(|e| -> Box<dyn Error> { yurl_error!(&format!("read {}: {}", path, e)) })?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))?.ok_or_else(|| -> Box<dyn Error> {
+        yurl_error!(&format!("no PEM private key in {}", path))
+    })
+}
+
+/// rustls connector, the server name overrides the url host when set.
+pub struct Connector {
+    config: Arc<ClientConfig>,
+    server_name: Option<String>,
+}
+
+impl ureq::TlsConnector for Connector {
+    fn connect(
+        &self,
+        dns_name: &str,
+        io: Box<dyn ureq::ReadWrite>,
+    ) -> Result<Box<dyn ureq::ReadWrite>, ureq::Error> {
+        let dns_name = self.server_name.as_deref().unwrap_or(dns_name);
+        self.config.connect(dns_name, io)
+    }
+}
+
+/// accepts every server certificate, used by `insecure: true`.
+#[derive(Debug)]
+struct NoVerifier;
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{RootCertStore, ServerConfig, ServerConnection, StreamOwned};
+    use rustls_pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+    use super::{Tls, TlsVersion};
+
+    fn ca() -> Certificate {
+        let mut params = CertificateParams::new(Vec::new());
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Certificate::from_params(params).unwrap()
+    }
+
+    /// certificate for the names signed by the ca, its der chain and key.
+    fn issue(
+        ca: &Certificate,
+        names: &[&str],
+    ) -> (
+        Certificate,
+        Vec<CertificateDer<'static>>,
+        PrivateKeyDer<'static>,
+    ) {
+        let names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        let cert = Certificate::from_params(CertificateParams::new(names)).unwrap();
+        let der = cert.serialize_der_with_signer(ca).unwrap();
+        let key = PrivatePkcs8KeyDer::from(cert.serialize_private_key_der());
+        (cert, vec![CertificateDer::from(der)], key.into())
+    }
+
+    /// https server on 127.0.0.1 answering `ok`, the SNI of every handshake is kept.
+    fn serve(config: ServerConfig) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("https://{}/", listener.local_addr().unwrap());
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let sni = names.clone();
+        let config = Arc::new(config);
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let connection = ServerConnection::new(config.clone()).unwrap();
+                let mut tls = StreamOwned::new(connection, stream);
+                // the handshake runs on the first read, failed ones are dropped
+                if tls.read(&mut [0u8; 4096]).is_err() {
+                    continue;
+                }
+                if let Some(name) = tls.conn.server_name() {
+                    sni.lock().unwrap().push(name.to_string());
+                }
+                let _ = tls.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                );
+                tls.conn.send_close_notify();
+                let _ = tls.flush();
+            }
+        });
+        (url, names)
+    }
+
+    fn get(tls: &Tls, url: &str) -> Result<String, Box<dyn Error>> {
+        let agent = ureq::AgentBuilder::new()
+            .tls_connector(tls.connector()?)
+            .build();
+        Ok(agent.get(url).call()?.into_string()?)
+    }
+
+    fn write(dir: &PathBuf, name: &str, pem: String) -> Option<String> {
+        let path = dir.join(name);
+        fs::write(&path, pem).unwrap();
+        Some(path.display().to_string())
+    }
+
+    #[test]
+    fn test_server_verification() {
+        let dir = std::env::temp_dir().join("yurl-tls-server-test");
+        fs::create_dir_all(&dir).unwrap();
+        let ca = ca();
+        let ca_file = write(&dir, "ca.pem", ca.serialize_pem().unwrap());
+        let (_, chain, key) = issue(&ca, &["api.internal"]);
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .unwrap();
+        let (url, sni) = serve(config);
+
+        let tls = Tls {
+            ca_file: ca_file.clone(),
+            server_name: Some("api.internal".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(get(&tls, &url).unwrap(), "ok");
+        assert_eq!(*sni.lock().unwrap(), ["api.internal"]);
+        // the certificate is not for the url host
+        let tls = Tls {
+            ca_file,
+            ..Default::default()
+        };
+        assert!(get(&tls, &url).is_err());
+        // the ca is not trusted
+        let tls = Tls {
+            server_name: Some("api.internal".to_string()),
+            ..Default::default()
+        };
+        assert!(get(&tls, &url).is_err());
+        let tls = Tls {
+            insecure: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(get(&tls, &url).unwrap(), "ok");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_client_cert() {
+        let dir = std::env::temp_dir().join("yurl-tls-client-test");
+        fs::create_dir_all(&dir).unwrap();
+        let ca = ca();
+        let ca_file = write(&dir, "ca.pem", ca.serialize_pem().unwrap());
+        let (_, chain, key) = issue(&ca, &["api.internal"]);
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from(ca.serialize_der().unwrap()))
+            .unwrap();
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+            .build()
+            .unwrap();
+        let config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(chain, key)
+            .unwrap();
+        let (url, _) = serve(config);
+
+        let (client, _, _) = issue(&ca, &["client"]);
+        let tls = Tls {
+            ca_file,
+            server_name: Some("api.internal".to_string()),
+            cert_file: write(
+                &dir,
+                "client.pem",
+                client.serialize_pem_with_signer(&ca).unwrap(),
+            ),
+            key_file: write(&dir, "client.key", client.serialize_private_key_pem()),
+            ..Default::default()
+        };
+        assert_eq!(get(&tls, &url).unwrap(), "ok");
+        let tls = Tls {
+            cert_file: None,
+            key_file: None,
+            ..tls
+        };
+        assert!(get(&tls, &url).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_client_config() {
+        let defaults = Tls {
+            insecure: Some(true),
+            min_version: Some(TlsVersion::TLS1_2),
+            ..Default::default()
+        };
+        let request = Tls {
+            min_version: Some(TlsVersion::TLS1_3),
+            server_name: Some("api.internal".to_string()),
+            ..Default::default()
+        };
+        let tls = defaults.merge(&request);
+        assert_eq!(tls.insecure, Some(true));
+        assert_eq!(tls.min_version, Some(TlsVersion::TLS1_3));
+        assert!(tls.connector().is_ok());
+        let tls = Tls {
+            cert_file: Some("client.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(tls.connector().is_err());
+    }
+}
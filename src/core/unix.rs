@@ -0,0 +1,359 @@
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use flate2::read::MultiGzDecoder;
+use url::Position;
+
+use crate::core::client::{parse_duration, Config, USER_AGENT};
+use crate::core::compress::ACCEPT_ENCODING;
+use crate::core::error::YurlError;
+use crate::yurl_error;
+
+const UNIX_SCHEME: &str = "unix://";
+/// Host header of requests sent over a unix socket
+const UNIX_HOST: &str = "localhost";
+/// brotli is only decoded by ureq, not by `send`
+const UNIX_ACCEPT_ENCODING: &str = "gzip, deflate";
+
+/// split `unix:///var/run/app.sock:/v1/health` into the socket path and
+/// the http url `http://localhost/v1/health`, other urls are returned unchanged.
+pub fn split_url(url: &str) -> Result<(Option<String>, String), Box<dyn Error>> {
+    let Some(rest) = url.strip_prefix(UNIX_SCHEME) else {
+        return Ok((None, url.to_string()));
+    };
+    let (socket, path) = match rest.find(":/") {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, "/"),
+    };
+    if socket.is_empty() {
+        return Err(yurl_error!(&format!("unix socket path missing: {}", url)));
+    }
+    Ok((Some(socket.to_string()), local_url(path)))
+}
+
+/// http url of a path sent over a unix socket.
+pub fn local_url(path: &str) -> String {
+    format!("http://{UNIX_HOST}{path}")
+}
+
+/// send the request over the unix socket of the config, one connection per request.
+/// HTTP/1.1 is written and read here, gzip bodies are decoded like ureq does.
+#[cfg(unix)]
+pub fn send(
+    config: &Config,
+    request: &ureq::Request,
+    body: Option<(&mut dyn Read, Option<u64>)>,
+) -> Result<ureq::Response, Box<dyn Error>> {
+    use std::os::unix::net::UnixStream;
+
+    let path = config.unix_socket.as_deref().unwrap_or_default();
+    let stream = UnixStream::connect(path).map_err(|e| -> Box<dyn Error> {
+        yurl_error!(&format!("connect unix socket {}: {}", path, e))
+    })?;
+    let timeout = config.timeout.as_deref().map(parse_duration).transpose()?;
+    let read_timeout = config
+        .read_timeout
+        .as_deref()
+        .map(parse_duration)
+        .transpose()?;
+    stream.set_read_timeout(read_timeout.or(timeout))?;
+    stream.set_write_timeout(timeout)?;
+
+    let url = request.request_url()?;
+    let url = url.as_url();
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\n",
+        request.method(),
+        &url[Position::BeforePath..Position::AfterQuery],
+        &url[Position::BeforeHost..Position::AfterPort]
+    );
+    let names = request.header_names();
+    for name in &names {
+        for value in request.all(name) {
+            let value =
+                match name.eq_ignore_ascii_case("accept-encoding") && value == ACCEPT_ENCODING {
+                    true => UNIX_ACCEPT_ENCODING,
+                    false => value,
+                };
+            if !name.eq_ignore_ascii_case("content-length") {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+    }
+    for (name, value) in [("user-agent", USER_AGENT), ("accept", "*/*")] {
+        if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            let value = match name {
+                "user-agent" => config.user_agent.as_deref().unwrap_or(value),
+                _ => value,
+            };
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    match &body {
+        Some((_, Some(length))) => head.push_str(&format!("Content-Length: {}\r\n", length)),
+        Some((_, None)) => head.push_str("Transfer-Encoding: chunked\r\n"),
+        None => {}
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    let mut writer = io::BufWriter::new(&stream);
+    writer.write_all(head.as_bytes())?;
+    match body {
+        Some((body, Some(_))) => _ = io::copy(body, &mut writer)?,
+        Some((body, None)) => {
+            let mut buf = [0u8; 8192];
+            loop {
+                let n = body.read(&mut buf)?;
+                write!(writer, "{:x}\r\n", n)?;
+                writer.write_all(&buf[..n])?;
+                writer.write_all(b"\r\n")?;
+                if n == 0 {
+                    break;
+                }
+            }
+        }
+        None => {}
+    }
+    writer.flush()?;
+    drop(writer);
+
+    let mut reader = BufReader::new(&stream);
+    let status_line = read_line(&mut reader)?;
+    let status: u16 = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| -> Box<dyn Error> {
+            yurl_error!(&format!(
+                "invalid status line from {}: {}",
+                path, status_line
+            ))
+        })?;
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.to_lowercase())
+    };
+    let mut body = Vec::new();
+    if request.method() == "HEAD" || matches!(status, 100..=199 | 204 | 304) {
+    } else if header("transfer-encoding").is_some_and(|t| t.contains("chunked")) {
+        read_chunked(&mut reader, &mut body)?;
+    } else if let Some(length) = header("content-length") {
+        (&mut reader).take(length.parse()?).read_to_end(&mut body)?;
+    } else {
+        reader.read_to_end(&mut body)?;
+    }
+    if header("content-encoding").is_some_and(|e| e == "gzip" || e == "x-gzip") {
+        let mut decoded = Vec::new();
+        MultiGzDecoder::new(body.as_slice()).read_to_end(&mut decoded)?;
+        body = decoded;
+        headers.retain(|(k, _)| {
+            !k.eq_ignore_ascii_case("content-encoding") && !k.eq_ignore_ascii_case("content-length")
+        });
+    }
+    headers.retain(|(k, _)| !k.eq_ignore_ascii_case("transfer-encoding"));
+    let mut response = http::Response::builder().status(status);
+    for (name, value) in headers {
+        response = response.header(name, value);
+    }
+    Ok(response.body(body)?.into())
+}
+
+#[cfg(not(unix))]
+pub fn send(
+    config: &Config,
+    _request: &ureq::Request,
+    _body: Option<(&mut dyn Read, Option<u64>)>,
+) -> Result<ureq::Response, Box<dyn Error>> {
+    Err(yurl_error!(&format!(
+        "unix socket {} is not supported on this platform",
+        config.unix_socket.as_deref().unwrap_or_default()
+    )))
+}
+
+/// a line without its line break, empty at the end of the stream.
+fn read_line(reader: &mut impl BufRead) -> Result<String, Box<dyn Error>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn read_chunked(reader: &mut impl BufRead, body: &mut Vec<u8>) -> Result<(), Box<dyn Error>> {
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = u64::from_str_radix(size, 16).map_err(|_| -> Box<dyn Error> {
+            yurl_error!(&format!("invalid chunk size: {}", line))
+        })?;
+        if size == 0 {
+            // trailers up to the empty line
+            while !read_line(reader)?.is_empty() {}
+            return Ok(());
+        }
+        reader.take(size).read_to_end(body)?;
+        read_line(reader)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_url;
+    #[cfg(unix)]
+    use crate::core::{
+        client::{Client, Defaults},
+        report::Record,
+        request::Request,
+    };
+
+    /// answer one connection of the listener, return the request head and body.
+    #[cfg(unix)]
+    fn serve(listener: &std::os::unix::net::UnixListener, response: &[u8]) -> String {
+        use std::io::{BufRead, BufReader, Read, Write};
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut head = String::new();
+        while reader.read_line(&mut head).unwrap() > 2 && !head.ends_with("\r\n\r\n") {}
+        let length = head
+            .lines()
+            .find_map(|l| {
+                l.to_lowercase()
+                    .strip_prefix("content-length:")
+                    .map(|v| v.trim().to_string())
+            })
+            .map_or(0, |v| v.parse().unwrap());
+        let mut body = vec![0u8; length];
+        reader.read_exact(&mut body).unwrap();
+        stream.write_all(response).unwrap();
+        head + &String::from_utf8(body).unwrap()
+    }
+
+    #[test]
+    fn test_split_url() {
+        let (socket, url) =
+            split_url("unix:///var/run/docker.sock:/v1.43/containers/json?all=1").unwrap();
+        assert_eq!(socket.as_deref(), Some("/var/run/docker.sock"));
+        assert_eq!(url, "http://localhost/v1.43/containers/json?all=1");
+        let (socket, url) = split_url("unix:///tmp/app.sock").unwrap();
+        assert_eq!(socket.as_deref(), Some("/tmp/app.sock"));
+        assert_eq!(url, "http://localhost/");
+        let (socket, url) = split_url("http://127.0.0.1:8080/a").unwrap();
+        assert_eq!(socket, None);
+        assert_eq!(url, "http://127.0.0.1:8080/a");
+        assert!(split_url("unix://:/v1").is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_send() {
+        use std::io::Write;
+        let dir = std::env::temp_dir().join("yurl-unix-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("app.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(b"hello unix").unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut done = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n",
+            gzip.len()
+        )
+        .into_bytes();
+        done.extend_from_slice(&gzip);
+        done.extend_from_slice(b"\r\n0\r\n\r\n");
+        let server = std::thread::spawn(move || {
+            let start = serve(
+                &listener,
+                b"HTTP/1.1 302 Found\r\nLocation: /done\r\nSet-Cookie: sid=1\r\nContent-Length: 0\r\n\r\n",
+            );
+            (start, serve(&listener, &done))
+        });
+        let request_yaml = format!(
+            r#"order: 1
+name: unix
+url: unix://{}:/start?all=1
+method: POST
+headers:
+params:
+  a: 1
+content_type: JSON
+response_type: TEXT
+"#,
+            socket.display()
+        );
+        let request: Request = serde_yaml::from_str(&request_yaml).unwrap();
+        let mut client = Client::new(Defaults::default());
+        let mut record = Record::new("", "unix");
+        assert_eq!(request.run(&mut client, &mut record).unwrap(), "hello unix");
+        let (start, done) = server.join().unwrap();
+        let start = start.to_lowercase();
+        assert!(
+            start.starts_with("post /start?all=1 http/1.1\r\n"),
+            "{start}"
+        );
+        assert!(start.contains("host: localhost\r\n"), "{start}");
+        assert!(start.contains("content-length: 7\r\n"), "{start}");
+        assert!(start.ends_with("\r\n\r\n{\"a\":1}"), "{start}");
+        let done = done.to_lowercase();
+        assert!(done.starts_with("get /done http/1.1\r\n"), "{done}");
+        assert!(done.contains("cookie: sid=1\r\n"), "{done}");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_send_redirect_out() {
+        let dir = std::env::temp_dir().join("yurl-unix-redirect-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("app.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let tcp = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let location = format!("http://{}/next", tcp.local_addr().unwrap());
+        let unix = std::thread::spawn(move || {
+            let response =
+                format!("HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\n\r\n");
+            serve(&listener, response.as_bytes())
+        });
+        let tcp = std::thread::spawn(move || {
+            use std::io::{BufRead, BufReader, Write};
+            let (mut stream, _) = tcp.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            while reader.read_line(&mut head).unwrap() > 2 && !head.ends_with("\r\n\r\n") {}
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .unwrap();
+            head
+        });
+        let request_yaml = format!(
+            r#"order: 1
+name: unix
+url: unix://{}:/start
+method: GET
+headers:
+params:
+content_type: URLENCODED
+response_type: TEXT
+"#,
+            socket.display()
+        );
+        let request: Request = serde_yaml::from_str(&request_yaml).unwrap();
+        let mut client = Client::new(Defaults::default());
+        let mut record = Record::new("", "unix");
+        assert_eq!(request.run(&mut client, &mut record).unwrap(), "ok");
+        assert!(unix.join().unwrap().starts_with("GET /start HTTP/1.1\r\n"));
+        assert!(tcp.join().unwrap().starts_with("GET /next HTTP/1.1\r\n"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
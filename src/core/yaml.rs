@@ -46,3 +46,21 @@ impl<'a> Yaml<'a> {
         };
     }
 }
+
+/// value of `key` for a `${var...}` expression, every variables document must
+/// define it as a scalar and the first one wins, none when there is no document.
+pub fn variable<'a>(
+    variables: &'a [Value],
+    key: &str,
+) -> Result<Option<&'a Value>, Box<dyn Error>> {
+    let mut found = None;
+    for variable in variables {
+        match Yaml::new(variable, key.to_string()).get_value() {
+            Ok(v @ (Value::Bool(_) | Value::Number(_) | Value::String(_))) => {
+                found = found.or(Some(v))
+            }
+            _ => return Err(yurl_error!(&format!("undefined variable: {}", key))),
+        }
+    }
+    Ok(found)
+}
@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::process;
 
 use colored::Colorize;
 
@@ -13,19 +14,43 @@ fn main() -> Result<(), Box<dyn Error>> {
         Commands::Run(arg) => match arg.run() {
             Ok(()) => {}
             Err(e) => {
-                error!(e.to_string())
+                error!(e.to_string());
+                process::exit(1)
             }
         },
         Commands::Function(arg) => match arg.run() {
             Ok(()) => {}
             Err(e) => {
-                error!(e.to_string())
+                error!(e.to_string());
+                process::exit(1)
             }
         },
         Commands::Generate(arg) => match arg.run() {
             Ok(()) => {}
             Err(e) => {
-                error!(e.to_string())
+                error!(e.to_string());
+                process::exit(1)
+            }
+        },
+        Commands::Check(arg) => match arg.run() {
+            Ok(()) => {}
+            Err(e) => {
+                error!(e.to_string());
+                process::exit(1)
+            }
+        },
+        Commands::Export(arg) => match arg.run() {
+            Ok(()) => {}
+            Err(e) => {
+                error!(e.to_string());
+                process::exit(1)
+            }
+        },
+        Commands::Schema(arg) => match arg.run() {
+            Ok(()) => {}
+            Err(e) => {
+                error!(e.to_string());
+                process::exit(1)
             }
         },
     }